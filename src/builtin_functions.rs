@@ -1,113 +1,670 @@
-use std::{collections::HashMap, fs, path::Path};
+use std::{cmp::Ordering, fs, iter, path::Path};
+
+use indexmap::IndexMap;
 
 use crate::{
+    context::Context,
+    encoding::{base64_decode, base64_encode, hex_decode, hex_encode},
     errors::RuntimeError,
-    executor::{execute_program, Command},
+    executor::{ensure_not_frozen, eval_expr, execute_program, get_var, resolve_index, Command},
+    json::from_json,
     parser::Expression,
-    scope::{get_var_from_scope_cloned, Scope},
-    variables::VariableValue,
+    scope::get_var_from_scope_cloned,
+    time::{format_time, parse_time},
+    variables::{VariableValue, MAX_RANGE_LEN},
 };
 
+/// Builtins that change the variable they are called with, such as `sort!(list)`.
+pub const MUTATING_BUILTIN_NAMES: [&str; 5] =
+    ["sort!", "reverse!", "remove!", "insert!", "remove_at!"];
+
+/// Builtins that can be called without a target.
+pub const BUILTIN_NAMES: [&str; 85] = [
+    "print",
+    "eprint",
+    "list",
+    "int",
+    "read",
+    "lines",
+    "range",
+    "downto",
+    "import",
+    "bytes_from_string",
+    "bytes_to_string",
+    "read_bytes",
+    "read_file",
+    "write_file",
+    "hex_encode",
+    "hex_decode",
+    "base64_encode",
+    "base64_decode",
+    "parse_json",
+    "wrapping_add",
+    "wrapping_sub",
+    "wrapping_mul",
+    "currency",
+    "now",
+    "format_time",
+    "parse_time",
+    "uuid",
+    "random",
+    "sqrt",
+    "abs",
+    "floor",
+    "ceil",
+    "round",
+    "pow",
+    "retry",
+    "min",
+    "max",
+    "sum",
+    "product",
+    "first",
+    "last",
+    "rest",
+    "init",
+    "enumerate",
+    "zip",
+    "sort",
+    "reverse",
+    "index_of",
+    "starts_with",
+    "ends_with",
+    "type",
+    "pipe_lines",
+    "parse_number",
+    "to_string",
+    "to_list",
+    "clone",
+    "format",
+    "assert",
+    "assert_throws",
+    "input",
+    "freeze",
+    "deep_freeze",
+    "is_frozen",
+    "split_once",
+    "rsplit",
+    "pad_left",
+    "pad_right",
+    "count_matches",
+    "capitalize",
+    "title_case",
+    "keys",
+    "values",
+    "words",
+    "contains",
+    "compact",
+    "split",
+    "join",
+    "to_upper",
+    "to_lower",
+    "trim",
+    "merge",
+    "merge_deep",
+    "replace",
+    "slice",
+    "chars",
+];
+
+/// Builtins that touch the filesystem, which fail if the context denies filesystem access.
+const FILESYSTEM_BUILTIN_NAMES: [&str; 5] =
+    ["read", "read_bytes", "read_file", "write_file", "import"];
+
+/// Builtins that are called like functions can also be called as methods of their first
+/// argument, so `xs.sum()` is `sum(xs)`.
+fn receiver_as_first_param(
+    name: &str,
+    target: &Option<VariableValue>,
+    params: &[VariableValue],
+) -> Option<Vec<VariableValue>> {
+    let receiver = target.as_ref().filter(|_| BUILTIN_NAMES.contains(&name))?;
+    Some(
+        iter::once(receiver.clone())
+            .chain(params.iter().cloned())
+            .collect(),
+    )
+}
+
 pub fn exec_builtin(
-    scope: &mut Scope,
+    ctx: &mut Context,
     name: &str,
     target: &Option<VariableValue>,
     params: &Vec<VariableValue>,
 ) -> Result<VariableValue, Command> {
+    if let Some(params) = receiver_as_first_param(name, target, params) {
+        return exec_builtin(ctx, name, &None, &params);
+    }
+    let is_frozen = |value: &VariableValue| matches!(value, VariableValue::Frozen(_));
+    if !matches!(name, "freeze" | "deep_freeze" | "is_frozen")
+        && (target.as_ref().is_some_and(is_frozen) || params.iter().any(is_frozen))
+    {
+        // builtins read frozen values like any other
+        let target = target.clone().map(VariableValue::thaw);
+        let params = params.iter().cloned().map(VariableValue::thaw).collect();
+        return exec_builtin(ctx, name, &target, &params);
+    }
+    if FILESYSTEM_BUILTIN_NAMES.contains(&name) && !ctx.filesystem_access() {
+        return Err(Command::Error(
+            format!("{} needs filesystem access, which is disabled", name).into(),
+        ));
+    }
     match name {
-        "print" => {
-            if let Some(print_target) = target {
-                println!("{}", print_target)
-            } else {
-                for (i, val) in params.iter().enumerate() {
-                    if i == 0 {
-                        print!("{}", val)
-                    } else {
-                        print!(" {}", val)
-                    }
-                }
-                println!();
+        "freeze" | "deep_freeze" | "is_frozen" => match params.as_slice() {
+            [value] => Ok(match name {
+                "freeze" => value.clone().freeze(),
+                "deep_freeze" => value.clone().deep_freeze(),
+                _ => VariableValue::Boolean(value.is_frozen()),
+            }),
+            _ => Err(Command::Error(format!("{} expects one value", name).into())),
+        },
+        "print" | "eprint" => print_values(ctx, name, params, " ", "\n"),
+        "to_string" => match params.as_slice() {
+            [value] => Ok(VariableValue::String(value.to_string())),
+            _ => Err(Command::Error("to_string expects one value".into())),
+        },
+        "format" => match params.as_slice() {
+            [VariableValue::String(template), args @ ..] => {
+                format_template(template, args).map(VariableValue::String)
             }
-            Ok(VariableValue::Unit)
+            _ => Err(Command::Error(
+                "format expects a template string and its arguments".into(),
+            )),
+        },
+        "clone" => match params.as_slice() {
+            [value] => Ok(value.clone().deep_thaw()),
+            _ => Err(Command::Error("clone expects one value".into())),
+        },
+        "to_list" => match params.as_slice() {
+            [VariableValue::List(list)] => Ok(VariableValue::List(list.clone())),
+            [VariableValue::String(s)] => Ok(VariableValue::List(
+                s.chars()
+                    .map(|c| VariableValue::String(c.to_string()))
+                    .collect(),
+            )),
+            [VariableValue::Object(obj)] => Ok(VariableValue::List(
+                obj.iter()
+                    .map(|(key, val)| {
+                        VariableValue::List(vec![
+                            VariableValue::String(key.to_string()),
+                            val.clone(),
+                        ])
+                    })
+                    .collect(),
+            )),
+            [VariableValue::Bytes(bytes)] => Ok(VariableValue::List(
+                bytes
+                    .iter()
+                    .map(|b| VariableValue::Int(*b as i64))
+                    .collect(),
+            )),
+            [value] => Err(Command::Error(
+                format!("Cannot convert {} to a list", value.get_type()).into(),
+            )),
+            _ => Err(Command::Error("to_list expects one value".into())),
+        },
+        "range" => match params.as_slice() {
+            [VariableValue::Int(stop)] => range_list(0, *stop, 1),
+            [VariableValue::Int(start), VariableValue::Int(stop)] => {
+                range_list(*start, *stop, if start > stop { -1 } else { 1 })
+            }
+            [VariableValue::Int(start), VariableValue::Int(stop), VariableValue::Int(step)] => {
+                range_list(*start, *stop, *step)
+            }
+            [_] | [_, _] | [_, _, _] => Err(Command::Error(
+                "Invalid parameter types for function 'range'".into(),
+            )),
+            _ => Err(Command::Error(
+                "Invalid parameter amount for function 'range'".into(),
+            )),
         }
-        "range" => {
-            if let (
-                VariableValue::Number(start),
-                VariableValue::Number(stop),
-                VariableValue::Number(step),
-            ) = match params.len() {
-                1 => Ok((
-                    &VariableValue::Number(0),
-                    &params[0],
-                    &VariableValue::Number(1),
-                )),
-                2 => Ok((&params[0], &params[1], &VariableValue::Number(1))),
-                3 => Ok((&params[0], &params[1], &params[2])),
-                _ => Err(Command::Error(
-                    "Invalid parameter amount for function 'range'".into(),
-                )),
-            }? {
-                let mut l = Vec::with_capacity(((stop - start) / step).try_into().unwrap());
-                for i in (*start..*stop).step_by((*step).try_into().unwrap()) {
-                    l.push(VariableValue::Number(i))
+        .map(VariableValue::List),
+        "downto" => {
+            if let [VariableValue::Int(start), VariableValue::Int(stop)] = params.as_slice() {
+                if start < stop {
+                    Err(Command::Error(
+                        format!("downto expects {} to be at least {}", start, stop).into(),
+                    ))
+                } else {
+                    // the range excludes its end, so it has to stop one below `stop`
+                    let end = stop.checked_sub(1).ok_or(Command::Error(
+                        format!("downto cannot count down to the smallest integer {}", stop).into(),
+                    ))?;
+                    range_list(*start, end, -1).map(VariableValue::List)
                 }
-                Ok(VariableValue::List(l))
             } else {
                 Err(Command::Error(
-                    "Invalid parameter types for function 'range'".into(),
+                    "Invalid parameters for function 'downto'".into(),
                 ))
             }
         }
+        "wrapping_add" | "wrapping_sub" | "wrapping_mul" => match params.as_slice() {
+            [VariableValue::Int(a), VariableValue::Int(b)] => Ok(VariableValue::Int(match name {
+                "wrapping_add" => a.wrapping_add(*b),
+                "wrapping_sub" => a.wrapping_sub(*b),
+                _ => a.wrapping_mul(*b),
+            })),
+            _ => Err(Command::Error(
+                format!("Function '{}' expects two integers", name).into(),
+            )),
+        },
+        "currency" => match params.as_slice() {
+            [VariableValue::Int(n), VariableValue::String(symbol)] => {
+                Ok(format_currency(*n as i128 * 100, symbol))
+            }
+            [VariableValue::Float(n), VariableValue::String(symbol)] => {
+                let cents = (n * 100.0).round();
+                // beyond this, `as i128` would saturate and print a wrong amount
+                if cents.is_finite() && cents.abs() < i128::MAX as f64 {
+                    Ok(format_currency(cents as i128, symbol))
+                } else {
+                    Err(Command::Error(
+                        format!("Cannot format {:e} as currency", n).into(),
+                    ))
+                }
+            }
+            _ => Err(Command::Error(
+                "Function 'currency' expects a number and a symbol".into(),
+            )),
+        }
+        .map(VariableValue::String),
+        "now" => match params.as_slice() {
+            [] => Ok(VariableValue::Int(ctx.now())),
+            _ => Err(Command::Error("now takes no arguments".into())),
+        },
+        "format_time" => match params.as_slice() {
+            [VariableValue::Int(epoch_ms), VariableValue::String(pattern)] => {
+                format_time(*epoch_ms, pattern)
+                    .map(VariableValue::String)
+                    .map_err(Command::Error)
+            }
+            _ => Err(Command::Error(
+                "Function 'format_time' expects a timestamp and a pattern".into(),
+            )),
+        },
+        "parse_time" => match params.as_slice() {
+            [VariableValue::String(input), VariableValue::String(pattern)] => {
+                parse_time(input, pattern)
+                    .map(VariableValue::Int)
+                    .map_err(Command::Error)
+            }
+            _ => Err(Command::Error(
+                "Function 'parse_time' expects a string and a pattern".into(),
+            )),
+        },
+        "uuid" => match params.as_slice() {
+            [] => Ok(VariableValue::String(ctx.rng().uuid())),
+            _ => Err(Command::Error("uuid takes no arguments".into())),
+        },
+        "random" => match params.as_slice() {
+            [] => Ok(VariableValue::Float(ctx.rng().next_f64())),
+            [VariableValue::Int(min), VariableValue::Int(max)] if min <= max => {
+                Ok(VariableValue::Int(ctx.rng().int_between(*min, *max)))
+            }
+            [VariableValue::Int(min), VariableValue::Int(max)] => Err(Command::Error(
+                format!("random expects {} to be at most {}", min, max).into(),
+            )),
+            _ => Err(Command::Error(
+                "random expects no arguments or two integers".into(),
+            )),
+        },
+        "sqrt" => match params.as_slice() {
+            [VariableValue::Int(n)] if *n >= 0 => Ok(VariableValue::Float((*n as f64).sqrt())),
+            [VariableValue::Float(n)] if *n >= 0.0 => Ok(VariableValue::Float(n.sqrt())),
+            [n @ (VariableValue::Int(_) | VariableValue::Float(_))] => Err(Command::Error(
+                format!("cannot take the square root of negative number {}", n).into(),
+            )),
+            _ => Err(Command::Error("sqrt expects a number".into())),
+        },
+        "abs" => match params.as_slice() {
+            [VariableValue::Int(n)] => n
+                .checked_abs()
+                .map(VariableValue::Int)
+                .ok_or(Command::Error(format!("abs of {} overflows", n).into())),
+            [VariableValue::Float(n)] => Ok(VariableValue::Float(n.abs())),
+            _ => Err(Command::Error("abs expects a number".into())),
+        },
+        "floor" | "ceil" | "round" => match params.as_slice() {
+            [VariableValue::Int(n)] => Ok(VariableValue::Int(*n)),
+            [VariableValue::Float(n)] => Ok(VariableValue::Float(match name {
+                "floor" => n.floor(),
+                "ceil" => n.ceil(),
+                _ => n.round(),
+            })),
+            _ => Err(Command::Error(format!("{} expects a number", name).into())),
+        },
+        "pow" => match params.as_slice() {
+            [base, exponent] => {
+                VariableValue::power(base.clone(), exponent.clone()).map_err(Command::Error)
+            }
+            _ => Err(Command::Error("pow expects a base and an exponent".into())),
+        },
+        "first" | "last" => match params.as_slice() {
+            [VariableValue::List(li)] => match name {
+                "first" => li.first(),
+                _ => li.last(),
+            }
+            .cloned()
+            .ok_or(Command::Error(format!("{} of an empty list", name).into())),
+            _ => Err(Command::Error(format!("{} expects a list", name).into())),
+        },
+        "rest" | "init" => match params.as_slice() {
+            [VariableValue::List(li)] => Ok(VariableValue::List(match name {
+                "rest" => li.iter().skip(1).cloned().collect(),
+                _ => li[..li.len().saturating_sub(1)].to_vec(),
+            })),
+            _ => Err(Command::Error(format!("{} expects a list", name).into())),
+        },
+        "enumerate" => match params.as_slice() {
+            [VariableValue::List(li)] => Ok(VariableValue::List(
+                li.iter()
+                    .enumerate()
+                    .map(|(i, el)| {
+                        VariableValue::List(vec![VariableValue::Int(i as i64), el.clone()])
+                    })
+                    .collect(),
+            )),
+            _ => Err(Command::Error("enumerate expects a list".into())),
+        },
+        "zip" => match params.as_slice() {
+            [VariableValue::List(a), VariableValue::List(b)] => Ok(VariableValue::List(
+                a.iter()
+                    .zip(b)
+                    .map(|(x, y)| VariableValue::List(vec![x.clone(), y.clone()]))
+                    .collect(),
+            )),
+            _ => Err(Command::Error("zip expects two lists".into())),
+        },
+        "min" | "max" => {
+            let values = match params.as_slice() {
+                [VariableValue::List(li)] if li.is_empty() => {
+                    return Err(Command::Error(format!("{} of an empty list", name).into()))
+                }
+                [VariableValue::List(li)] => li,
+                [_, _, ..] => params,
+                _ => {
+                    return Err(Command::Error(
+                        format!("{} expects a list or at least two values", name).into(),
+                    ))
+                }
+            };
+            let wanted = if name == "min" {
+                Ordering::Less
+            } else {
+                Ordering::Greater
+            };
+            let mut best = &values[0];
+            for (i, value) in values.iter().enumerate() {
+                let order = VariableValue::compare(value, best, name).map_err(|_| {
+                    Command::Error(
+                        format!(
+                            "{} cannot compare element {} ({}) with {}",
+                            name, i, value, best
+                        )
+                        .into(),
+                    )
+                })?;
+                if order == Some(wanted) {
+                    best = value;
+                }
+            }
+            Ok(best.clone())
+        }
+        "sum" | "product" => match params.as_slice() {
+            [VariableValue::List(li)] => {
+                let mut result = VariableValue::Int(if name == "sum" { 0 } else { 1 });
+                for value in li {
+                    if !matches!(value, VariableValue::Int(_) | VariableValue::Float(_)) {
+                        return Err(Command::Error(
+                            format!("{} expects a list of numbers, got {}", name, value).into(),
+                        ));
+                    }
+                    result = if name == "sum" {
+                        VariableValue::add(result, value.clone())
+                    } else {
+                        VariableValue::multiply(result, value.clone())
+                    }
+                    .map_err(Command::Error)?;
+                }
+                Ok(result)
+            }
+            _ => Err(Command::Error(format!("{} expects a list", name).into())),
+        },
+        "assert" => match params.as_slice() {
+            [VariableValue::Boolean(true)]
+            | [VariableValue::Boolean(true), VariableValue::String(_)] => Ok(VariableValue::Unit),
+            [VariableValue::Boolean(false)] => Err(Command::Error("assertion failed".into())),
+            [VariableValue::Boolean(false), VariableValue::String(message)] => Err(Command::Error(
+                format!("assertion failed: {}", message).into(),
+            )),
+            [cond] | [cond, VariableValue::String(_)] => Err(Command::Error(
+                format!("assert expects a boolean condition, got {}", cond).into(),
+            )),
+            _ => Err(Command::Error(
+                "assert expects a condition and an optional message".into(),
+            )),
+        },
+        "assert_throws" => match params.as_slice() {
+            [function @ VariableValue::Function(_, _)] => match function.call(ctx, Vec::new()) {
+                Err(Command::Error(e)) => Ok(VariableValue::String(e.0)),
+                Err(cmd) => Err(cmd),
+                Ok(val) => Err(Command::Error(
+                    format!("expected an error, but the function returned {}", val).into(),
+                )),
+            },
+            _ => Err(Command::Error("assert_throws expects a function".into())),
+        },
+        "retry" => match params.as_slice() {
+            [function @ VariableValue::Function(_, _), VariableValue::Int(attempts)]
+                if *attempts >= 1 =>
+            {
+                let mut result = function.call(ctx, Vec::new());
+                for _ in 1..*attempts {
+                    match result {
+                        Err(Command::Error(_)) => result = function.call(ctx, Vec::new()),
+                        _ => break,
+                    }
+                }
+                result
+            }
+            [VariableValue::Function(_, _), VariableValue::Int(_)] => {
+                Err(Command::Error("retry needs at least one attempt".into()))
+            }
+            _ => Err(Command::Error(
+                "retry expects a function and a number of attempts".into(),
+            )),
+        },
+        "type" => match params.as_slice() {
+            // integers and floats print differently, but are both numbers to scripts
+            [value] => Ok(VariableValue::String(match value.thawed() {
+                VariableValue::Int(_) | VariableValue::Float(_) => "number".to_string(),
+                value => value.get_type().to_lowercase(),
+            })),
+            _ => Err(Command::Error("type expects one value".into())),
+        },
         "int" => {
             if params.len() != 1 {
                 Err(Command::Error(
                     "Invalid parameter amount for function 'int'".into(),
                 ))
-            } else if let Some(VariableValue::String(val)) = params.get(0) {
-                str::parse::<i32>(val)
+            } else if let Some(VariableValue::String(val)) = params.first() {
+                str::parse::<i64>(val)
                     .map_err(|_| Command::Error("Cannot parse str to int".into()))
-                    .map(|v| VariableValue::Number(v))
+                    .map(VariableValue::Int)
+            } else if let Some(VariableValue::Float(val)) = params.first() {
+                Ok(VariableValue::Int(val.trunc() as i64))
+            } else if let Some(VariableValue::Int(val)) = params.first() {
+                Ok(VariableValue::Int(*val))
             } else {
                 Err(Command::Error("Cannot parse to int".into()))
             }
         }
+        "input" => {
+            match params.as_slice() {
+                [] => {}
+                [VariableValue::String(prompt)] => {
+                    ctx.write_output(prompt).map_err(Command::Error)?
+                }
+                _ => return Err(Command::Error("input expects an optional prompt".into())),
+            }
+            Ok(ctx
+                .read_line()
+                .map_err(Command::Error)?
+                .map_or(VariableValue::None, VariableValue::String))
+        }
+        "pipe_lines" => match params.as_slice() {
+            [function @ VariableValue::Function(_, _)] => {
+                while let Some(line) = ctx.read_line().map_err(Command::Error)? {
+                    match function.call(ctx, vec![VariableValue::String(line)])? {
+                        VariableValue::Unit => {}
+                        result => ctx
+                            .write_output(&format!("{}\n", result))
+                            .map_err(Command::Error)?,
+                    }
+                }
+                Ok(VariableValue::Unit)
+            }
+            _ => Err(Command::Error("pipe_lines expects a function".into())),
+        },
+        "parse_number" => match params.as_slice() {
+            [VariableValue::String(s)] => {
+                let trimmed = s.trim();
+                if let Ok(n) = trimmed.parse::<i64>() {
+                    Ok(VariableValue::Int(n))
+                } else {
+                    trimmed
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|n| n.is_finite())
+                        .map(VariableValue::Float)
+                        .ok_or(Command::Error(
+                            format!("cannot parse {:?} as a number", s).into(),
+                        ))
+                }
+            }
+            _ => Err(Command::Error("parse_number expects a string".into())),
+        },
         "read" => {
             if params.len() != 1 {
                 Err(Command::Error(
                     "Invalid parameter amount for function 'read'".into(),
                 ))
-            } else if let Some(VariableValue::String(val)) = params.get(0) {
+            } else if let Some(VariableValue::String(val)) = params.first() {
                 fs::read_to_string(val)
                     .map_err(|_| Command::Error("Cannot read file".into()))
-                    .map(|v| VariableValue::String(v))
+                    .map(VariableValue::String)
             } else {
                 Err(Command::Error("Param is not a string".into()))
             }
         }
-        "lines" => {
-            if params.len() != 0 {
-                Err(Command::Error(
-                    "Invalid parameter amount for function 'lines'".into(),
-                ))
-            } else if let Some(VariableValue::String(val)) = target {
-                val.lines()
-                    .map(|v| Ok(VariableValue::String(v.trim().to_string())))
-                    .collect::<Result<Vec<VariableValue>, Command>>()
-                    .map(|l| VariableValue::List(l))
+        "read_file" => match params.as_slice() {
+            [VariableValue::String(path)] => fs::read_to_string(path)
+                .map(VariableValue::String)
+                .map_err(|e| Command::Error(format!("Cannot read {}: {}", path, e).into())),
+            _ => Err(Command::Error("read_file expects a path".into())),
+        },
+        "write_file" => match params.as_slice() {
+            [VariableValue::String(path), VariableValue::String(contents)] => {
+                fs::write(path, contents)
+                    .map(|_| VariableValue::Unit)
+                    .map_err(|e| Command::Error(format!("Cannot write {}: {}", path, e).into()))
+            }
+            _ => Err(Command::Error(
+                "write_file expects a path and a string".into(),
+            )),
+        },
+        "read_bytes" => match params.as_slice() {
+            [VariableValue::String(path)] => fs::read(path)
+                .map(VariableValue::Bytes)
+                .map_err(|_| Command::Error("Cannot read file".into())),
+            _ => Err(Command::Error(
+                "Invalid parameters for function 'read_bytes'".into(),
+            )),
+        },
+        "bytes_from_string" => match params.as_slice() {
+            [VariableValue::String(s)] => Ok(VariableValue::Bytes(s.as_bytes().to_vec())),
+            _ => Err(Command::Error(
+                "Invalid parameters for function 'bytes_from_string'".into(),
+            )),
+        },
+        "bytes_to_string" => match params.as_slice() {
+            [VariableValue::Bytes(bytes)] => String::from_utf8(bytes.clone())
+                .map(VariableValue::String)
+                .map_err(|_| Command::Error("Bytes are not valid UTF-8".into())),
+            _ => Err(Command::Error(
+                "Invalid parameters for function 'bytes_to_string'".into(),
+            )),
+        },
+        "hex_encode" | "base64_encode" => {
+            let encode = if name == "hex_encode" {
+                hex_encode
+            } else {
+                base64_encode
+            };
+            match params.as_slice() {
+                [VariableValue::Bytes(bytes)] => Ok(VariableValue::String(encode(bytes))),
+                [VariableValue::String(s)] => Ok(VariableValue::String(encode(s.as_bytes()))),
+                _ => Err(Command::Error(
+                    format!("Invalid parameters for function '{}'", name).into(),
+                )),
+            }
+        }
+        "hex_decode" | "base64_decode" => {
+            let decode = if name == "hex_decode" {
+                hex_decode
             } else {
-                Err(Command::Error("target is not a string".into()))
+                base64_decode
+            };
+            match params.as_slice() {
+                [VariableValue::String(s)] => {
+                    decode(s).map(VariableValue::Bytes).map_err(Command::Error)
+                }
+                _ => Err(Command::Error(
+                    format!("Invalid parameters for function '{}'", name).into(),
+                )),
             }
         }
+        "parse_json" => match params.as_slice() {
+            [VariableValue::String(json)] => from_json(json).map_err(Command::Error),
+            _ => Err(Command::Error(
+                "Invalid parameters for function 'parse_json'".into(),
+            )),
+        },
+        "lines" => match params.as_slice() {
+            [VariableValue::String(s)] => Ok(VariableValue::List(
+                s.lines()
+                    .map(|l| VariableValue::String(l.trim().to_string()))
+                    .collect(),
+            )),
+            _ => Err(Command::Error("lines expects a string".into())),
+        },
+        "chars" => match params.as_slice() {
+            [VariableValue::String(s)] => Ok(VariableValue::List(
+                s.chars()
+                    .map(|c| VariableValue::String(c.to_string()))
+                    .collect(),
+            )),
+            _ => Err(Command::Error("chars expects a string".into())),
+        },
+        "words" => match params.as_slice() {
+            [VariableValue::String(s)] => Ok(VariableValue::List(
+                s.split_whitespace()
+                    .map(|word| VariableValue::String(word.to_string()))
+                    .collect(),
+            )),
+            _ => Err(Command::Error("words expects a string".into())),
+        },
         "import" => {
             if params.len() != 1 {
                 Err(Command::Error(
                     "Invalid parameter amount for function 'import'".into(),
                 ))
-            } else if let Some(VariableValue::String(val)) = params.get(0) {
-                let cwd_str = get_var_from_scope_cloned(scope, "cwd").and_then(|v| match v {
-                    VariableValue::String(s) => Ok(s),
-                    _ => Err(Command::Error("".into())),
-                })?;
+            } else if let Some(VariableValue::String(val)) = params.first() {
+                let cwd_str =
+                    get_var_from_scope_cloned(&ctx.scope, "cwd").and_then(|v| match v {
+                        VariableValue::String(s) => Ok(s),
+                        _ => Err(Command::Error("".into())),
+                    })?;
                 let cwd = Path::new(&cwd_str);
 
                 let new_cwd = cwd
@@ -120,9 +677,10 @@ pub fn exec_builtin(
                     .to_string();
                 let program = fs::read_to_string(cwd.join(val))
                     .map_err(|_| Command::Error("Cannot read file".into()))?;
-                let (result, _) = execute_program(program, new_cwd)
-                    .map_err(|e| Command::Error(RuntimeError(e.to_string())))?;
-                Ok(result)
+                let outer_scope = std::mem::replace(&mut ctx.scope, Context::new(&new_cwd).scope);
+                let result = execute_program(ctx, &program);
+                ctx.scope = outer_scope;
+                result.map_err(|e| Command::Error(RuntimeError::from(e.to_string())))
             } else {
                 Err(Command::Error("Param is not a string".into()))
             }
@@ -132,7 +690,7 @@ pub fn exec_builtin(
                 Err(Command::Error(
                     "Invalid parameter amount for function 'list'".into(),
                 ))
-            } else if let Some(VariableValue::String(val)) = params.get(0) {
+            } else if let Some(VariableValue::String(val)) = params.first() {
                 Ok(VariableValue::List(
                     val.chars()
                         .map(|c| VariableValue::String(c.to_string()))
@@ -147,13 +705,13 @@ pub fn exec_builtin(
                 Err(Command::Error(
                     "Invalid parameter amount for function 'obj'".into(),
                 ))
-            } else if let Some(VariableValue::List(val)) = params.get(0) {
+            } else if let Some(VariableValue::List(val)) = params.first() {
                 Ok(VariableValue::Object(
                     val.iter()
                         .map(|c| {
                             if let VariableValue::List(a) = c {
                                 if let (Some(VariableValue::String(var_name)), Some(val)) =
-                                    (a.get(0), a.get(1))
+                                    (a.first(), a.get(1))
                                 {
                                     Ok((var_name.to_string(), val.clone()))
                                 } else {
@@ -165,83 +723,300 @@ pub fn exec_builtin(
                                 Err(Command::Error("".into()))
                             }
                         })
-                        .collect::<Result<HashMap<String, VariableValue>, Command>>()?,
+                        .collect::<Result<IndexMap<String, VariableValue>, Command>>()?,
                 ))
             } else {
                 Err(Command::Error("Cannot convert to object".into()))
             }
         }
-        "split" => {
-            if let (Some(VariableValue::String(split)), Some(VariableValue::String(splitter))) =
-                (target, params.get(0))
-            {
+        "split" => match params.as_slice() {
+            [VariableValue::String(split), VariableValue::String(splitter)] => {
+                let parts: Vec<String> = if splitter.is_empty() {
+                    split.chars().map(String::from).collect()
+                } else {
+                    split.split(splitter).map(str::to_string).collect()
+                };
                 Ok(VariableValue::List(
-                    split
-                        .split(splitter)
-                        .map(|s| VariableValue::String(s.to_string()))
-                        .collect(),
+                    parts.into_iter().map(VariableValue::String).collect(),
                 ))
-            } else {
-                Err(Command::Error("Invalid arguments for method split".into()))
             }
-        }
+            _ => Err(Command::Error(
+                "split expects a string and a separator".into(),
+            )),
+        },
+        "slice" => match params.as_slice() {
+            [VariableValue::String(s), VariableValue::Int(start), VariableValue::Int(end)] => {
+                let chars: Vec<char> = s.chars().collect();
+                let range = slice_range(chars.len(), *start, *end);
+                Ok(VariableValue::String(chars[range].iter().collect()))
+            }
+            [VariableValue::List(li), VariableValue::Int(start), VariableValue::Int(end)] => Ok(
+                VariableValue::List(li[slice_range(li.len(), *start, *end)].to_vec()),
+            ),
+            _ => Err(Command::Error(
+                "slice expects a string or list and two integers".into(),
+            )),
+        },
+        "join" => match params.as_slice() {
+            [VariableValue::List(li), VariableValue::String(separator)] => {
+                Ok(VariableValue::String(
+                    li.iter()
+                        .map(|el| match el {
+                            VariableValue::String(s) => s.clone(),
+                            other => other.to_string(),
+                        })
+                        .collect::<Vec<String>>()
+                        .join(separator),
+                ))
+            }
+            _ => Err(Command::Error("join expects a list and a separator".into())),
+        },
+        "split_once" | "rsplit" => match params.as_slice() {
+            [VariableValue::String(s), VariableValue::String(separator)] => {
+                let parts = if name == "split_once" {
+                    s.split_once(separator.as_str())
+                } else {
+                    s.rsplit_once(separator.as_str())
+                };
+                Ok(parts.map_or(VariableValue::Unit, |(before, after)| {
+                    VariableValue::List(vec![
+                        VariableValue::String(before.to_string()),
+                        VariableValue::String(after.to_string()),
+                    ])
+                }))
+            }
+            _ => Err(Command::Error(
+                format!("{} expects a string and a separator", name).into(),
+            )),
+        },
+        "replace" => match params.as_slice() {
+            [VariableValue::String(_), VariableValue::String(from), VariableValue::String(_)]
+                if from.is_empty() =>
+            {
+                Err(Command::Error(
+                    "replace needs a non-empty string to replace".into(),
+                ))
+            }
+            [VariableValue::String(s), VariableValue::String(from), VariableValue::String(to)] => {
+                Ok(VariableValue::String(s.replace(from.as_str(), to)))
+            }
+            _ => Err(Command::Error("replace expects three strings".into())),
+        },
+        "to_upper" | "to_lower" | "trim" => match params.as_slice() {
+            [VariableValue::String(s)] => Ok(VariableValue::String(match name {
+                "to_upper" => s.to_uppercase(),
+                "to_lower" => s.to_lowercase(),
+                _ => s.trim().to_string(),
+            })),
+            _ => Err(Command::Error(format!("{} expects a string", name).into())),
+        },
+        "capitalize" | "title_case" => match params.as_slice() {
+            [VariableValue::String(s)] => {
+                let mut result = String::new();
+                let mut prev: Option<char> = None;
+                for c in s.chars() {
+                    let starts_word = match name {
+                        "capitalize" => prev.is_none(),
+                        _ => prev.is_none_or(char::is_whitespace),
+                    };
+                    if starts_word {
+                        result.extend(c.to_uppercase());
+                    } else {
+                        result.push(c);
+                    }
+                    prev = Some(c);
+                }
+                Ok(VariableValue::String(result))
+            }
+            _ => Err(Command::Error(format!("{} expects a string", name).into())),
+        },
+        "count_matches" => match params.as_slice() {
+            [VariableValue::String(_), VariableValue::String(needle)] if needle.is_empty() => Err(
+                Command::Error("count_matches needs a non-empty needle".into()),
+            ),
+            [VariableValue::String(s), VariableValue::String(needle)] => {
+                Ok(VariableValue::Int(s.matches(needle.as_str()).count() as i64))
+            }
+            _ => Err(Command::Error(
+                "count_matches expects a string and a needle".into(),
+            )),
+        },
+        "pad_left" | "pad_right" => match params.as_slice() {
+            [VariableValue::String(s), VariableValue::Int(width), VariableValue::String(fill)] => {
+                let mut fill_chars = fill.chars();
+                let (Some(fill), None) = (fill_chars.next(), fill_chars.next()) else {
+                    return Err(Command::Error(
+                        format!("{} expects a single fill character, got {:?}", name, fill).into(),
+                    ));
+                };
+                let missing = usize::try_from(*width)
+                    .unwrap_or(0)
+                    .saturating_sub(s.chars().count());
+                let padding: String = std::iter::repeat_n(fill, missing).collect();
+                Ok(VariableValue::String(if name == "pad_left" {
+                    padding + s
+                } else {
+                    s.to_string() + &padding
+                }))
+            }
+            _ => Err(Command::Error(
+                format!("{} expects a string, a width and a fill character", name).into(),
+            )),
+        },
         "map" => {
-            if let Some(VariableValue::Function(_, _)) = params.get(0) {
+            if let Some(VariableValue::Function(_, _)) = params.first() {
                 match target {
                     Some(VariableValue::List(li)) => li
                         .iter()
-                        .map(|el| params[0].call(scope, vec![el.clone()]))
+                        .map(|el| params[0].call(ctx, vec![el.clone()]))
                         .collect::<Result<Vec<VariableValue>, Command>>()
-                        .map(|v| VariableValue::List(v)),
+                        .map(VariableValue::List),
                     Some(VariableValue::Object(li)) => li
                         .iter()
                         .map(|(key, el)| {
                             params[0]
                                 .call(
-                                    scope,
+                                    ctx,
                                     vec![VariableValue::String(key.to_string()), el.clone()],
                                 )
                                 .map(|res| (key.to_string(), res))
                         })
-                        .collect::<Result<HashMap<String, VariableValue>, Command>>()
-                        .map(|v| VariableValue::Object(v)),
+                        .collect::<Result<IndexMap<String, VariableValue>, Command>>()
+                        .map(VariableValue::Object),
                     Some(VariableValue::String(li)) => li
                         .chars()
-                        .map(|el| {
-                            params[0].call(scope, vec![VariableValue::String(el.to_string())])
-                        })
+                        .map(|el| params[0].call(ctx, vec![VariableValue::String(el.to_string())]))
                         .collect::<Result<Vec<VariableValue>, Command>>()
-                        .map(|v| VariableValue::List(v)),
+                        .map(VariableValue::List),
                     _ => Err(Command::Error("invalid target for map".into())),
                 }
             } else {
                 Err(Command::Error("Invalid arguments for method map".into()))
             }
         }
+        "sort" => match params.as_slice() {
+            [VariableValue::List(li)] => {
+                let mut li = li.clone();
+                sort_values(&mut li)?;
+                Ok(VariableValue::List(li))
+            }
+            _ => Err(Command::Error("sort expects a list".into())),
+        },
+        "reverse" => match params.as_slice() {
+            [VariableValue::List(li)] => {
+                Ok(VariableValue::List(li.iter().rev().cloned().collect()))
+            }
+            [VariableValue::String(s)] => Ok(VariableValue::String(s.chars().rev().collect())),
+            _ => Err(Command::Error("reverse expects a list or a string".into())),
+        },
+        "compact" => match params.as_slice() {
+            [VariableValue::List(li)] => Ok(VariableValue::List(
+                li.iter()
+                    .filter(|el| !matches!(el, VariableValue::Unit | VariableValue::None))
+                    .cloned()
+                    .collect(),
+            )),
+            _ => Err(Command::Error("compact expects a list".into())),
+        },
+        "contains" => match params.as_slice() {
+            [VariableValue::List(li), item] => {
+                for el in li {
+                    if let VariableValue::Boolean(true) =
+                        VariableValue::equals(el.clone(), item.clone()).map_err(Command::Error)?
+                    {
+                        return Ok(VariableValue::Boolean(true));
+                    }
+                }
+                Ok(VariableValue::Boolean(false))
+            }
+            [VariableValue::String(s), VariableValue::String(sub)] => {
+                Ok(VariableValue::Boolean(s.contains(sub.as_str())))
+            }
+            [VariableValue::Object(obj), VariableValue::String(key)] => {
+                Ok(VariableValue::Boolean(obj.contains_key(key)))
+            }
+            _ => Err(Command::Error(
+                "contains expects a list, a string or an object and an item".into(),
+            )),
+        },
+        "starts_with" | "ends_with" => match params.as_slice() {
+            [VariableValue::String(s), VariableValue::String(affix)] => {
+                Ok(VariableValue::Boolean(if name == "starts_with" {
+                    s.starts_with(affix.as_str())
+                } else {
+                    s.ends_with(affix.as_str())
+                }))
+            }
+            _ => Err(Command::Error(
+                format!("{} expects two strings", name).into(),
+            )),
+        },
+        "index_of" => match params.as_slice() {
+            [VariableValue::List(li), item] => {
+                for (i, el) in li.iter().enumerate() {
+                    if let VariableValue::Boolean(true) =
+                        VariableValue::equals(el.clone(), item.clone()).map_err(Command::Error)?
+                    {
+                        return Ok(VariableValue::Int(i as i64));
+                    }
+                }
+                Ok(VariableValue::None)
+            }
+            [VariableValue::String(s), VariableValue::String(sub)] => {
+                // strings are indexed by characters, not bytes
+                Ok(s.find(sub.as_str())
+                    .map_or(VariableValue::None, |byte_index| {
+                        VariableValue::Int(s[..byte_index].chars().count() as i64)
+                    }))
+            }
+            _ => Err(Command::Error(
+                "index_of expects a list and an item or two strings".into(),
+            )),
+        },
+        "merge" | "merge_deep" => match params.as_slice() {
+            [VariableValue::Object(base), VariableValue::Object(other)] => Ok(
+                VariableValue::Object(merge_objects(base, other, name == "merge_deep")),
+            ),
+            _ => Err(Command::Error(
+                format!("{} expects two objects", name).into(),
+            )),
+        },
+        "keys" | "values" => match params.as_slice() {
+            [VariableValue::Object(obj)] => Ok(VariableValue::List(
+                obj.iter()
+                    .map(|(key, val)| match name {
+                        "keys" => VariableValue::String(key.to_string()),
+                        _ => val.clone(),
+                    })
+                    .collect(),
+            )),
+            _ => Err(Command::Error(format!("{} expects an object", name).into())),
+        },
         "len" => match target {
-            Some(VariableValue::List(li)) => Ok(VariableValue::Number(li.len() as i32)),
-            Some(VariableValue::Object(li)) => Ok(VariableValue::Number(li.len() as i32)),
-            Some(VariableValue::String(li)) => Ok(VariableValue::Number(li.len() as i32)),
+            Some(VariableValue::List(li)) => Ok(VariableValue::Int(li.len() as i64)),
+            Some(VariableValue::Object(li)) => Ok(VariableValue::Int(li.len() as i64)),
+            Some(VariableValue::String(li)) => Ok(VariableValue::Int(li.len() as i64)),
+            Some(VariableValue::Bytes(bytes)) => Ok(VariableValue::Int(bytes.len() as i64)),
             _ => Err(Command::Error("invalid target for map".into())),
         },
         "filter" => {
-            if let Some(VariableValue::Function(_, _)) = params.get(0) {
+            if let Some(VariableValue::Function(_, _)) = params.first() {
                 match target {
                     Some(VariableValue::List(li)) => li
                         .iter()
-                        .filter_map(|el| match params[0].call(scope, vec![el.clone()]) {
+                        .filter_map(|el| match params[0].call(ctx, vec![el.clone()]) {
                             Err(e) => Some(Err(e)),
                             Ok(VariableValue::Boolean(b)) if b => Some(Ok(el.clone())),
                             Ok(VariableValue::Boolean(_)) => None,
                             Ok(_) => Some(Err(Command::Error("not a bool".into()))),
                         })
                         .collect::<Result<Vec<VariableValue>, Command>>()
-                        .map(|v| VariableValue::List(v)),
+                        .map(VariableValue::List),
                     Some(VariableValue::Object(li)) => li
                         .iter()
                         .filter_map(|(key, el)| {
                             match params[0].call(
-                                scope,
+                                ctx,
                                 vec![VariableValue::String(key.to_string()), el.clone()],
                             ) {
                                 Err(e) => Some(Err(e)),
@@ -252,13 +1027,12 @@ pub fn exec_builtin(
                                 Ok(_) => Some(Err(Command::Error("not a bool".into()))),
                             }
                         })
-                        .collect::<Result<HashMap<String, VariableValue>, Command>>()
-                        .map(|v| VariableValue::Object(v)),
+                        .collect::<Result<IndexMap<String, VariableValue>, Command>>()
+                        .map(VariableValue::Object),
                     Some(VariableValue::String(li)) => li
                         .chars()
                         .filter_map(|el| {
-                            match params[0].call(scope, vec![VariableValue::String(el.to_string())])
-                            {
+                            match params[0].call(ctx, vec![VariableValue::String(el.to_string())]) {
                                 Err(e) => Some(Err(e)),
                                 Ok(VariableValue::Boolean(b)) if b => {
                                     Some(Ok(VariableValue::String(el.to_string())))
@@ -268,27 +1042,302 @@ pub fn exec_builtin(
                             }
                         })
                         .collect::<Result<Vec<VariableValue>, Command>>()
-                        .map(|v| VariableValue::List(v)),
+                        .map(VariableValue::List),
                     _ => Err(Command::Error("invalid target for map".into())),
                 }
             } else {
                 Err(Command::Error("Invalid arguments for method filter".into()))
             }
         }
-        _ => Err(Command::Error("not a builtin function".into())),
+        _ => ctx
+            .call_native_fn(name, params.clone())
+            .unwrap_or(Err("not a builtin function".into()))
+            .map_err(Command::Error),
+    }
+}
+
+/// Calls a builtin with named arguments, which only some builtins accept.
+pub fn exec_builtin_named(
+    ctx: &mut Context,
+    name: &str,
+    target: &Option<VariableValue>,
+    params: &[VariableValue],
+    named: &[(String, VariableValue)],
+) -> Result<VariableValue, Command> {
+    if let Some(params) = receiver_as_first_param(name, target, params) {
+        return exec_builtin_named(ctx, name, &None, &params, named);
+    }
+    match name {
+        "print" | "eprint" => {
+            let (mut sep, mut end) = (" ", "\n");
+            for (key, val) in named {
+                match (key.as_str(), val) {
+                    ("sep", VariableValue::String(s)) => sep = s,
+                    ("end", VariableValue::String(s)) => end = s,
+                    ("sep" | "end", v) => {
+                        return Err(Command::Error(
+                            format!("{} must be a string, got {}", key, v).into(),
+                        ))
+                    }
+                    _ => {
+                        return Err(Command::Error(
+                            format!("{} has no argument named '{}'", name, key).into(),
+                        ))
+                    }
+                }
+            }
+            print_values(ctx, name, params, sep, end)
+        }
+        _ => Err(Command::Error(
+            format!("{} takes no named arguments", name).into(),
+        )),
+    }
+}
+
+/// Calls a builtin that changes the variable passed to it instead of returning a new value.
+pub fn exec_mutating_builtin(
+    ctx: &mut Context,
+    name: &str,
+    params: &[Expression],
+) -> Result<VariableValue, Command> {
+    let [Expression::Reference(ref_expr), args @ ..] = params else {
+        return Err(Command::Error(
+            format!("{} expects a variable as its first argument", name).into(),
+        ));
+    };
+    let args = args
+        .iter()
+        .map(|arg| eval_expr(ctx, arg))
+        .collect::<Result<Vec<VariableValue>, Command>>()?;
+    let value = get_var(ctx, ref_expr)?;
+    ensure_not_frozen(value)?;
+    match (name, value, args.as_slice()) {
+        ("sort!", VariableValue::List(li), []) => sort_values(li).map(|_| VariableValue::Unit),
+        ("reverse!", VariableValue::List(li), []) => {
+            li.reverse();
+            Ok(VariableValue::Unit)
+        }
+        ("remove!", VariableValue::Object(obj), [VariableValue::String(key)]) => {
+            obj.shift_remove(key).ok_or(Command::Error(
+                format!("Object has no field '{}'", key).into(),
+            ))
+        }
+        ("insert!", VariableValue::List(li), [VariableValue::Int(i), item]) => {
+            // inserting at the length appends
+            let position = resolve_index(*i, li.len() + 1).map_err(|_| {
+                Command::Error(
+                    format!(
+                        "Cannot insert at index {} into a list of length {}",
+                        i,
+                        li.len()
+                    )
+                    .into(),
+                )
+            })?;
+            li.insert(position, item.clone());
+            Ok(VariableValue::Unit)
+        }
+        ("remove_at!", VariableValue::List(li), [VariableValue::Int(i)]) => {
+            let i = resolve_index(*i, li.len())?;
+            Ok(li.remove(i))
+        }
+        ("insert!", v, [_, _]) => Err(Command::Error(
+            format!("insert! expects a list, an index and a value, got {}", v).into(),
+        )),
+        ("remove_at!", v, [_]) => Err(Command::Error(
+            format!("remove_at! expects a list and an index, got {}", v).into(),
+        )),
+        ("sort!" | "reverse!", v, []) => Err(Command::Error(
+            format!("{} expects a list, got {}", name, v).into(),
+        )),
+        ("remove!", v, [_]) => Err(Command::Error(
+            format!("remove! expects an object and a field name, got {}", v).into(),
+        )),
+        _ => Err(Command::Error(
+            format!("Invalid parameter amount for function '{}'", name).into(),
+        )),
+    }
+}
+
+/// Sorts numbers or strings in ascending order. The values are checked before sorting, so the
+/// list is left as it was if they can't be compared.
+fn sort_values(values: &mut [VariableValue]) -> Result<(), Command> {
+    for pair in values.windows(2) {
+        VariableValue::compare(&pair[0], &pair[1], "<").map_err(Command::Error)?;
+    }
+    values.sort_by(|a, b| {
+        VariableValue::compare(a, b, "<")
+            .ok()
+            .flatten()
+            .unwrap_or(Ordering::Equal)
+    });
+    Ok(())
+}
+
+/// `print` writes to the output of the context, `eprint` to its error output.
+fn print_values(
+    ctx: &mut Context,
+    name: &str,
+    params: &[VariableValue],
+    sep: &str,
+    end: &str,
+) -> Result<VariableValue, Command> {
+    let mut s = params
+        .iter()
+        .map(|val| val.to_string())
+        .collect::<Vec<String>>()
+        .join(sep);
+    s.push_str(end);
+    if name == "eprint" {
+        ctx.write_error_output(&s)
+    } else {
+        ctx.write_output(&s)
+    }
+    .map_err(Command::Error)?;
+    Ok(VariableValue::Unit)
+}
+
+/// Replaces each `{}` in the template with the next argument, converted like `join` does.
+/// `{{` and `}}` stand for literal braces.
+fn format_template(template: &str, args: &[VariableValue]) -> Result<String, Command> {
+    let mut s = String::with_capacity(template.len());
+    let mut args_iter = args.iter();
+    let mut placeholders = 0;
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('{', Some('{')) | ('}', Some('}')) => {
+                chars.next();
+                s.push(c);
+            }
+            ('{', Some('}')) => {
+                chars.next();
+                placeholders += 1;
+                match args_iter.next() {
+                    Some(VariableValue::String(arg)) => s.push_str(arg),
+                    Some(arg) => s.push_str(&arg.to_string()),
+                    None => (),
+                }
+            }
+            ('{', _) | ('}', _) => {
+                return Err(Command::Error(
+                    format!(
+                        "unmatched '{}' in format string, use '{}{}' for a brace",
+                        c, c, c
+                    )
+                    .into(),
+                ))
+            }
+            (c, _) => s.push(c),
+        }
+    }
+    if placeholders != args.len() {
+        return Err(Command::Error(
+            format!(
+                "format string has {} placeholder{}, but got {} argument{}",
+                placeholders,
+                if placeholders == 1 { "" } else { "s" },
+                args.len(),
+                if args.len() == 1 { "" } else { "s" },
+            )
+            .into(),
+        ));
+    }
+    Ok(s)
+}
+
+fn range_list(start: i64, stop: i64, step: i64) -> Result<Vec<VariableValue>, Command> {
+    if step == 0 {
+        return Err(Command::Error("range step cannot be zero".into()));
+    }
+    if (step > 0 && start > stop) || (step < 0 && start < stop) {
+        return Err(Command::Error(
+            format!(
+                "range step {} does not match the direction from {} to {}",
+                step, start, stop
+            )
+            .into(),
+        ));
+    }
+    let len = (stop as i128 - start as i128 + step as i128 - step.signum() as i128) / step as i128;
+    if len > MAX_RANGE_LEN {
+        return Err(Command::Error(
+            format!("range {}..{} is too long to be a list", start, stop).into(),
+        ));
+    }
+    let mut l = Vec::new();
+    let mut i = Some(start);
+    while let Some(n) = i.filter(|n| (step > 0 && *n < stop) || (step < 0 && *n > stop)) {
+        l.push(VariableValue::Int(n));
+        i = n.checked_add(step);
+    }
+    Ok(l)
+}
+
+/// The half-open range `start..end` of a sequence of length `len`. Negative bounds count from
+/// the end and bounds outside of the sequence are clamped, so the range is always valid.
+fn slice_range(len: usize, start: i64, end: i64) -> std::ops::Range<usize> {
+    let clamp = |i: i64| {
+        let i = if i < 0 { i + len as i64 } else { i };
+        i.clamp(0, len as i64) as usize
+    };
+    let (start, end) = (clamp(start), clamp(end));
+    start..end.max(start)
+}
+
+/// Returns a new object with the fields of `base` overridden by those of `other`. With `deep`,
+/// fields that are objects on both sides are merged recursively instead of replaced.
+fn merge_objects(
+    base: &IndexMap<String, VariableValue>,
+    other: &IndexMap<String, VariableValue>,
+    deep: bool,
+) -> IndexMap<String, VariableValue> {
+    let mut merged = base.clone();
+    for (key, val) in other {
+        let val = match (merged.get(key).map(VariableValue::thawed), val.thawed()) {
+            (Some(VariableValue::Object(a)), VariableValue::Object(b)) if deep => {
+                VariableValue::Object(merge_objects(a, b, deep))
+            }
+            _ => val.clone(),
+        };
+        merged.insert(key.clone(), val);
+    }
+    merged
+}
+
+/// Formats an amount in cents as e.g. `-$1,234.56`.
+fn format_currency(cents: i128, symbol: &str) -> String {
+    let units = (cents.abs() / 100).to_string();
+    let mut grouped = String::new();
+    for (i, digit) in units.chars().enumerate() {
+        if i > 0 && (units.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
     }
+    format!(
+        "{}{}{}.{:02}",
+        if cents < 0 { "-" } else { "" },
+        symbol,
+        grouped,
+        cents.abs() % 100
+    )
+}
+
+pub fn native_fn_value(name: &str) -> VariableValue {
+    VariableValue::Function(
+        Vec::new(),
+        Box::new(Expression::BuiltinFunctionCall(
+            name.to_string(),
+            None,
+            Vec::new(),
+        )),
+    )
 }
 
 pub fn is_builtin(name: &str, target: Option<&VariableValue>) -> Option<VariableValue> {
-    if match (target, name) {
-        (_, "print") => true,
-        (_, "list") => true,
-        (_, "int") => true,
-        (_, "read") => true,
-        (_, "lines") => true,
-        (_, "range") => true,
-        (_, "import") => true,
-        (Some(VariableValue::String(_)), "split") => true,
+    if match (target.map(VariableValue::thawed), name) {
+        (_, name) if BUILTIN_NAMES.contains(&name) => true,
         (Some(VariableValue::String(_)), "map") => true,
         (Some(VariableValue::List(_)), "map") => true,
         (Some(VariableValue::Object(_)), "map") => true,
@@ -298,6 +1347,8 @@ pub fn is_builtin(name: &str, target: Option<&VariableValue>) -> Option<Variable
         (Some(VariableValue::String(_)), "len") => true,
         (Some(VariableValue::List(_)), "len") => true,
         (Some(VariableValue::Object(_)), "len") => true,
+        (Some(VariableValue::List(_)), "reverse") => true,
+        (Some(VariableValue::Bytes(_)), "len") => true,
         (_, _) => false,
     } {
         Some(VariableValue::Function(
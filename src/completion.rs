@@ -0,0 +1,156 @@
+use std::ops::Range;
+
+use crate::*;
+
+/// A variable found while scanning the source. `initializer` is the byte range of the
+/// expression a `let` binds it to.
+pub(crate) struct Definition {
+    pub name: String,
+    pub initializer: Option<Range<usize>>,
+}
+
+/// Returns the identifiers that could complete the word ending at `offset` (a byte offset into
+/// `program`). Candidates are the variables defined before the cursor that are still in scope,
+/// `cwd` and the builtin functions, sorted alphabetically.
+pub fn complete(program: &str, offset: usize) -> Vec<String> {
+    let mut offset = offset.min(program.len());
+    while !program.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    let word_start = program[..offset]
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| c.is_alphanumeric() || *c == '_')
+        .last()
+        .map(|(i, _)| i)
+        .unwrap_or(offset);
+    let prefix = &program[word_start..offset];
+
+    let mut candidates: Vec<String> = defined_variables(program, word_start)
+        .into_iter()
+        .map(|definition| definition.name)
+        .chain(["cwd".to_string()])
+        .chain(BUILTIN_NAMES.iter().map(|name| name.to_string()))
+        .chain(MUTATING_BUILTIN_NAMES.iter().map(|name| name.to_string()))
+        .filter(|name| name.starts_with(prefix))
+        .collect();
+    candidates.sort();
+    candidates.dedup();
+    candidates
+}
+
+/// Collects the variables that are in scope at byte offset `end` of `program`, from the
+/// outermost to the innermost scope.
+pub(crate) fn defined_variables(program: &str, end: usize) -> Vec<Definition> {
+    let tokens = classify_tokens(program);
+    let text = |i: usize| tokens.get(i).map(|t| &program[t.span.clone()]);
+    let variable = |name| Definition {
+        name,
+        initializer: None,
+    };
+
+    let mut scopes: Vec<Vec<Definition>> = vec![Vec::new()];
+    let mut closure_params = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() && tokens[i].span.start < end {
+        match text(i) {
+            Some("let" | "const") => {
+                if let Some(name) = identifier_at(&tokens, i + 1, program) {
+                    let initializer = (text(i + 2) == Some("=") && i + 3 < tokens.len())
+                        .then(|| initializer_span(&tokens, i + 3, program));
+                    scopes
+                        .last_mut()
+                        .unwrap()
+                        .push(Definition { name, initializer });
+                }
+            }
+            Some("for") => {
+                if text(i + 1) == Some("[") {
+                    let mut j = i + 2;
+                    while let Some(var_name) = identifier_at(&tokens, j, program) {
+                        scopes.last_mut().unwrap().push(variable(var_name));
+                        if text(j + 1) != Some(",") {
+                            break;
+                        }
+                        j += 2;
+                    }
+                } else if let Some(var_name) = identifier_at(&tokens, i + 1, program) {
+                    scopes.last_mut().unwrap().push(variable(var_name));
+                }
+            }
+            Some("|") => {
+                let mut params = Vec::new();
+                let mut j = i + 1;
+                if text(j) == Some("...") {
+                    j += 1;
+                }
+                while let Some(var_name) = identifier_at(&tokens, j, program) {
+                    params.push(variable(var_name));
+                    j += 1;
+                    if text(j) == Some(":") {
+                        j = skip_default(&tokens, j + 1, program);
+                    }
+                    if text(j) != Some(",") {
+                        break;
+                    }
+                    j += 1;
+                    if text(j) == Some("...") {
+                        j += 1;
+                    }
+                }
+                if text(j) == Some("|") {
+                    closure_params = params;
+                    i = j;
+                }
+            }
+            Some("{") => scopes.push(std::mem::take(&mut closure_params)),
+            Some("}") if scopes.len() > 1 => {
+                scopes.pop();
+            }
+            Some(";") => closure_params.clear(),
+            _ => (),
+        }
+        i += 1;
+    }
+    scopes.push(closure_params);
+    scopes.into_iter().flatten().collect()
+}
+
+/// The span from token `start` up to the next semicolon outside of any brackets.
+fn initializer_span(tokens: &[HighlightToken], start: usize, program: &str) -> Range<usize> {
+    let mut depth = 0;
+    let mut end = start;
+    for (i, token) in tokens.iter().enumerate().skip(start) {
+        match &program[token.span.clone()] {
+            "{" | "(" | "[" => depth += 1,
+            "}" | ")" | "]" if depth == 0 => break,
+            "}" | ")" | "]" => depth -= 1,
+            ";" if depth == 0 => break,
+            _ => (),
+        }
+        end = i;
+    }
+    tokens[start].span.start..tokens[end].span.end
+}
+
+/// The index of the comma or bar that ends the default value of a parameter starting at token
+/// `start`.
+fn skip_default(tokens: &[HighlightToken], start: usize, program: &str) -> usize {
+    let mut depth = 0;
+    for (i, token) in tokens.iter().enumerate().skip(start) {
+        match &program[token.span.clone()] {
+            "{" | "(" | "[" => depth += 1,
+            "}" | ")" | "]" => depth -= 1,
+            "," | "|" if depth == 0 => return i,
+            _ => (),
+        }
+    }
+    tokens.len()
+}
+
+fn identifier_at(tokens: &[HighlightToken], i: usize, program: &str) -> Option<String> {
+    tokens
+        .get(i)
+        .filter(|t| t.class == TokenClass::Identifier)
+        .map(|t| program[t.span.clone()].to_string())
+}
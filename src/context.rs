@@ -0,0 +1,249 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    io::{self, BufRead, BufReader, Write},
+    rc::Rc,
+};
+
+use crate::{random::Rng, time::system_time_ms, *};
+
+pub type NativeFunction = Box<dyn Fn(Vec<VariableValue>) -> Result<VariableValue, RuntimeError>>;
+
+/// Returns the current time in milliseconds since the Unix epoch.
+pub type Clock = Box<dyn Fn() -> i64>;
+
+/// How lists, strings and bytes treat indices that are floats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IndexPolicy {
+    /// Float indices are an error.
+    #[default]
+    Strict,
+    /// Float indices are truncated towards zero, so `l[1.9]` is `l[1]`.
+    Truncate,
+}
+
+/// The state a program is executed in: its variables and everything the host configured.
+pub struct Context {
+    pub scope: Scope,
+    native_functions: HashMap<String, NativeFunction>,
+    input: Box<dyn BufRead>,
+    output: Box<dyn Write>,
+    error_output: Box<dyn Write>,
+    rng: Rng,
+    clock: Clock,
+    call_depth: usize,
+    max_call_depth: usize,
+    fuel: Option<u64>,
+    index_policy: IndexPolicy,
+    truthy_conditions: bool,
+    filesystem_access: bool,
+}
+
+impl Context {
+    pub fn new(cwd: &str) -> Self {
+        let mut scope = Scope::new();
+        enter_scope(&mut scope);
+        define_var_in_scope(&mut scope, "cwd", VariableValue::String(cwd.to_string()))
+            .expect("the new scope should be empty");
+        Context {
+            scope,
+            native_functions: HashMap::new(),
+            input: Box::new(BufReader::new(io::stdin())),
+            output: Box::new(io::stdout()),
+            error_output: Box::new(io::stderr()),
+            rng: Rng::from_time(),
+            clock: Box::new(system_time_ms),
+            call_depth: 0,
+            max_call_depth: 1000,
+            fuel: None,
+            index_policy: IndexPolicy::default(),
+            truthy_conditions: false,
+            filesystem_access: true,
+        }
+    }
+
+    /// Replaces the reader `input` and `pipe_lines` read from, which is stdin by default.
+    pub fn with_input(mut self, input: Box<dyn BufRead>) -> Self {
+        self.input = input;
+        self
+    }
+
+    /// Replaces the writer `print` writes to, which is stdout by default.
+    pub fn with_output(mut self, output: Box<dyn Write>) -> Self {
+        self.output = output;
+        self
+    }
+
+    /// Replaces the writer `eprint` writes to, which is stderr by default.
+    pub fn with_error_output(mut self, error_output: Box<dyn Write>) -> Self {
+        self.error_output = error_output;
+        self
+    }
+
+    /// Seeds the generator behind `random` and `uuid`, which is seeded from the clock by
+    /// default, so that runs are reproducible.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = Rng::new(seed);
+        self
+    }
+
+    /// Replaces the clock `now` reads, which is the system clock by default, e.g. with a fixed
+    /// time for tests.
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Limits how deeply function calls may nest, which is 1000 by default. Deeper calls fail
+    /// with an error. Calls move to a new native stack when the current one runs low, so the
+    /// thread running the program doesn't overflow its stack at any limit.
+    pub fn with_max_call_depth(mut self, depth: usize) -> Self {
+        self.max_call_depth = depth;
+        self
+    }
+
+    /// Limits how many statements and expressions a program may evaluate, across all runs
+    /// against this context. Once the budget is used up, execution stops with an error. There
+    /// is no limit by default.
+    pub fn with_fuel(mut self, fuel: u64) -> Self {
+        self.fuel = Some(fuel);
+        self
+    }
+
+    /// How much of the budget set with `with_fuel` is left.
+    pub fn remaining_fuel(&self) -> Option<u64> {
+        self.fuel
+    }
+
+    /// Sets whether float indices are rejected, which is the default, or truncated.
+    pub fn with_index_policy(mut self, policy: IndexPolicy) -> Self {
+        self.index_policy = policy;
+        self
+    }
+
+    pub(crate) fn index_policy(&self) -> IndexPolicy {
+        self.index_policy
+    }
+
+    /// Lets conditions of `if` and `while` be any value, which is then tested with
+    /// `VariableValue::is_truthy`. By default conditions must be booleans.
+    pub fn with_truthy_conditions(mut self, truthy: bool) -> Self {
+        self.truthy_conditions = truthy;
+        self
+    }
+
+    pub(crate) fn truthy_conditions(&self) -> bool {
+        self.truthy_conditions
+    }
+
+    /// Sets whether scripts may read and write files and import other scripts, which they may
+    /// by default. Without access, `read`, `read_bytes`, `read_file`, `write_file` and `import`
+    /// fail.
+    pub fn with_filesystem_access(mut self, allowed: bool) -> Self {
+        self.filesystem_access = allowed;
+        self
+    }
+
+    pub(crate) fn filesystem_access(&self) -> bool {
+        self.filesystem_access
+    }
+
+    pub(crate) fn consume_fuel(&mut self) -> Result<(), RuntimeError> {
+        match &mut self.fuel {
+            Some(0) => Err("execution budget exhausted".into()),
+            Some(fuel) => {
+                *fuel -= 1;
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
+    pub(crate) fn enter_call(&mut self) -> Result<(), RuntimeError> {
+        if self.call_depth >= self.max_call_depth {
+            return Err("maximum recursion depth exceeded".into());
+        }
+        self.call_depth += 1;
+        Ok(())
+    }
+
+    pub(crate) fn exit_call(&mut self) {
+        self.call_depth -= 1;
+    }
+
+    pub(crate) fn rng(&mut self) -> &mut Rng {
+        &mut self.rng
+    }
+
+    pub(crate) fn now(&self) -> i64 {
+        (self.clock)()
+    }
+
+    /// Reads the next line of input without its line ending, or `None` at the end of the input.
+    pub fn read_line(&mut self) -> Result<Option<String>, RuntimeError> {
+        let mut line = String::new();
+        match self.input.read_line(&mut line) {
+            Ok(0) => Ok(None),
+            Ok(_) => {
+                let len = line.trim_end_matches(['\n', '\r']).len();
+                line.truncate(len);
+                Ok(Some(line))
+            }
+            Err(e) => Err(RuntimeError::from(format!("Cannot read input: {}", e))),
+        }
+    }
+
+    pub fn write_output(&mut self, s: &str) -> Result<(), RuntimeError> {
+        self.output
+            .write_all(s.as_bytes())
+            .and_then(|_| self.output.flush())
+            .map_err(|e| RuntimeError::from(format!("Cannot write output: {}", e)))
+    }
+
+    pub fn write_error_output(&mut self, s: &str) -> Result<(), RuntimeError> {
+        self.error_output
+            .write_all(s.as_bytes())
+            .and_then(|_| self.error_output.flush())
+            .map_err(|e| RuntimeError::from(format!("Cannot write error output: {}", e)))
+    }
+
+    /// Makes `function` callable from scripts as `name`. Variables and builtins of the same
+    /// name take precedence.
+    pub fn register_fn(&mut self, name: &str, function: NativeFunction) {
+        self.native_functions.insert(name.to_string(), function);
+    }
+
+    pub fn is_native_fn(&self, name: &str) -> bool {
+        self.native_functions.contains_key(name)
+    }
+
+    pub fn call_native_fn(
+        &self,
+        name: &str,
+        params: Vec<VariableValue>,
+    ) -> Option<Result<VariableValue, RuntimeError>> {
+        self.native_functions
+            .get(name)
+            .map(|function| function(params))
+    }
+}
+
+/// An in-memory writer for capturing the output of a program. Clones share the same buffer.
+#[derive(Debug, Clone, Default)]
+pub struct OutputBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl OutputBuffer {
+    pub fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.0.borrow()).to_string()
+    }
+}
+
+impl Write for OutputBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
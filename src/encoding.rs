@@ -0,0 +1,72 @@
+use crate::*;
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub(crate) fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub(crate) fn hex_decode(s: &str) -> Result<Vec<u8>, RuntimeError> {
+    if !s.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(RuntimeError::from(format!(
+            "'{}' is not a valid hex string",
+            s
+        )));
+    }
+    if !s.len().is_multiple_of(2) {
+        return Err(RuntimeError::from(format!(
+            "hex string '{}' has an odd number of digits",
+            s
+        )));
+    }
+    Ok((0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+        .collect())
+}
+
+/// Standard base64 with `=` padding.
+pub(crate) fn base64_encode(bytes: &[u8]) -> String {
+    let mut s = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, b)| n | (*b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                s.push(BASE64_ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                s.push('=');
+            }
+        }
+    }
+    s
+}
+
+pub(crate) fn base64_decode(s: &str) -> Result<Vec<u8>, RuntimeError> {
+    let invalid = || RuntimeError::from(format!("'{}' is not a valid base64 string", s));
+    let data = s.trim_end_matches('=');
+    if !s.len().is_multiple_of(4) || s.len() - data.len() > 2 {
+        return Err(invalid());
+    }
+    let mut bytes = Vec::with_capacity(data.len() * 3 / 4);
+    for chunk in data.as_bytes().chunks(4) {
+        let mut n = 0u32;
+        for (i, c) in chunk.iter().enumerate() {
+            let value = BASE64_ALPHABET
+                .iter()
+                .position(|a| a == c)
+                .ok_or_else(invalid)?;
+            n |= (value as u32) << (18 - 6 * i);
+        }
+        if chunk.len() == 1 {
+            return Err(invalid());
+        }
+        for i in 0..chunk.len() - 1 {
+            bytes.push((n >> (16 - 8 * i)) as u8);
+        }
+    }
+    Ok(bytes)
+}
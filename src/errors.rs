@@ -1,10 +1,19 @@
 use std::fmt::{Debug, Display};
 
+use crate::tokenizer::Span;
+
+/// Where an error occurred. The source line is filled in once the program text is known.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    pub span: Span,
+    pub source_line: Option<String>,
+}
+
 #[derive(Debug)]
-pub struct RuntimeError(pub String);
+pub struct RuntimeError(pub String, pub Option<Location>);
 
 #[derive(Debug)]
-pub struct SyntaxError(pub String);
+pub struct SyntaxError(pub String, pub Option<Location>);
 
 #[derive(Debug)]
 pub struct ClientError(pub String);
@@ -16,13 +25,111 @@ pub enum Error {
     R(RuntimeError),
 }
 
+impl SyntaxError {
+    /// Sets the position of the error unless a more precise one is already known.
+    pub fn at(self, span: Span) -> Self {
+        SyntaxError(self.0, self.1.or(Some(Location::new(span))))
+    }
+}
+
+impl RuntimeError {
+    /// Sets the position of the error unless a more precise one is already known.
+    pub fn at(self, span: Span) -> Self {
+        RuntimeError(self.0, self.1.or(Some(Location::new(span))))
+    }
+}
+
+impl Location {
+    fn new(span: Span) -> Self {
+        Location {
+            span,
+            source_line: None,
+        }
+    }
+
+    fn with_source(self, source: &str) -> Self {
+        Location {
+            source_line: self
+                .source_line
+                .or_else(|| source.lines().nth(self.span.line - 1).map(str::to_string)),
+            ..self
+        }
+    }
+}
+
+impl Error {
+    /// Attaches the source line the error points at, so it can be shown with the message.
+    pub fn with_source(self, source: &str) -> Self {
+        match self {
+            Error::S(SyntaxError(message, location)) => Error::S(SyntaxError(
+                message,
+                location.map(|l| l.with_source(source)),
+            )),
+            Error::R(RuntimeError(message, location)) => Error::R(RuntimeError(
+                message,
+                location.map(|l| l.with_source(source)),
+            )),
+            e => e,
+        }
+    }
+}
+
+/// Writes the message, the position and, if known, the source line with a caret under the
+/// column:
+///
+/// ```text
+/// missing operand after '+' at line 2, column 11
+///  2 | let y = x +;
+///    |           ^
+/// ```
+fn fmt_located(
+    f: &mut std::fmt::Formatter<'_>,
+    message: &str,
+    location: &Option<Location>,
+) -> std::fmt::Result {
+    f.write_str(message)?;
+    let Some(Location { span, source_line }) = location else {
+        return Ok(());
+    };
+    write!(f, " at line {}, column {}", span.line, span.column)?;
+    if let Some(line) = source_line {
+        let gutter = span.line.to_string();
+        let padding: String = line
+            .chars()
+            .take(span.column - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        write!(
+            f,
+            "\n {} | {}\n {} | {}^",
+            gutter,
+            line,
+            " ".repeat(gutter.len()),
+            padding
+        )?;
+    }
+    Ok(())
+}
+
+impl Display for SyntaxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fmt_located(f, &self.0, &self.1)
+    }
+}
+
+impl Display for RuntimeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fmt_located(f, &self.0, &self.1)
+    }
+}
+
 impl Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str(match self {
-            Error::C(c) => &c.0,
-            Error::S(c) => &c.0,
-            Error::R(c) => &c.0,
-        })
+        match self {
+            Error::C(c) => f.write_str(&c.0),
+            Error::S(c) => Display::fmt(c, f),
+            Error::R(c) => Display::fmt(c, f),
+        }
     }
 }
 
@@ -31,7 +138,7 @@ where
     T: Into<String>,
 {
     fn from(value: T) -> Self {
-        SyntaxError(value.into())
+        SyntaxError(value.into(), None)
     }
 }
 
@@ -49,7 +156,7 @@ where
     T: Into<String>,
 {
     fn from(value: T) -> Self {
-        RuntimeError(value.into())
+        RuntimeError(value.into(), None)
     }
 }
 
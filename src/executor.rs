@@ -1,7 +1,10 @@
-use std::collections::HashMap;
+use std::env;
 
 use crate::{
-    builtin_functions::{exec_builtin, is_builtin},
+    builtin_functions::{
+        exec_builtin, exec_builtin_named, exec_mutating_builtin, is_builtin, native_fn_value,
+        MUTATING_BUILTIN_NAMES,
+    },
     *,
 };
 
@@ -13,226 +16,446 @@ pub enum Command {
     Error(RuntimeError),
 }
 
-pub fn execute_program(
-    program: String,
-    cwd: String,
-) -> Result<(VariableValue, Vec<HashMap<String, VariableValue>>), Error> {
-    let tokens = tokenize(&program)?;
-    let reduced = reduce_brackets_and_parenths(&tokens)?;
+impl Command {
+    /// Locates an error at `span` unless it already has a position.
+    pub fn at(self, span: Span) -> Self {
+        match self {
+            Command::Error(e) => Command::Error(e.at(span)),
+            cmd => cmd,
+        }
+    }
+}
+
+pub fn execute_program(ctx: &mut Context, program: &str) -> Result<VariableValue, Error> {
+    execute_unlocated(ctx, program).map_err(|e| e.with_source(program))
+}
+
+fn execute_unlocated(ctx: &mut Context, program: &str) -> Result<VariableValue, Error> {
+    let statements = parse_program(program).map_err(|mut errors| errors.remove(0))?;
+    execute_statements(ctx, &statements)
+}
 
-    let statements = get_statements(&reduced)?;
-    let mut scope = vec![HashMap::new()];
-    scope.get_mut(0).unwrap().insert("cwd".to_string(), VariableValue::String(cwd));
-    let r = match exec_stmnts(&mut scope, &statements) {
+/// Runs the statements of a parsed program and returns the value it evaluates to.
+pub fn execute_statements(
+    ctx: &mut Context,
+    statements: &[Statement],
+) -> Result<VariableValue, Error> {
+    match exec_stmnts(ctx, statements) {
         Ok(v) => Ok(v.unwrap_or(VariableValue::Unit)),
         Err(Command::Error(e)) => Err(e.into()),
         Err(Command::Return(v)) => Ok(v),
-        Err(cmd) => Err(RuntimeError(format!("Command {:?} cannot leave module", cmd)).into()),
-    };
-    r.map(|v| (v, scope))
+        Err(cmd) => {
+            Err(RuntimeError::from(format!("Command {:?} cannot leave module", cmd)).into())
+        }
+    }
+}
+
+/// Runs a program and returns the value it evaluates to.
+/// Imports are resolved relative to the current working directory.
+pub fn run(program: &str) -> Result<VariableValue, Error> {
+    let cwd = env::current_dir().map_err(|e| ClientError(e.to_string()))?;
+    execute_program(&mut Context::new(&cwd.to_string_lossy()), program)
 }
 
 pub fn exec_stmnts(
-    scope: &mut Scope,
+    ctx: &mut Context,
     stmnts: &[Statement],
 ) -> Result<Option<VariableValue>, Command> {
     for stmnt in stmnts {
-        if let Some(return_val) = exec_stmnt(scope, stmnt)? {
+        if let Some(return_val) = exec_stmnt(ctx, stmnt)? {
             return Ok(Some(return_val));
         }
     }
     Ok(None)
 }
 
-pub fn exec_stmnt(scope: &mut Scope, stmnt: &Statement) -> Result<Option<VariableValue>, Command> {
+pub fn exec_stmnt(ctx: &mut Context, stmnt: &Statement) -> Result<Option<VariableValue>, Command> {
     info!("exec: {:?}", stmnt);
+    ctx.consume_fuel().map_err(Command::Error)?;
     match stmnt {
-        Statement::VariableDefinition(var, val) => define_var(scope, var, val).map(|_| None),
-        Statement::VariableAssignment(var, val) => assign_var(scope, var, val).map(|_| None),
-        Statement::Expr(expr) => eval_expr(scope, expr).map(|_| None),
-        Statement::Return(expr) => Err(Command::Return(eval_expr(scope, expr)?)),
-        Statement::Break(expr) => Err(Command::Break(eval_expr(scope, expr)?)),
+        Statement::VariableDefinition(var, val) => define_var(ctx, var, val).map(|_| None),
+        Statement::ConstDefinition(var, val) => define_const(ctx, var, val).map(|_| None),
+        Statement::VariableAssignment(var, val) => assign_var(ctx, var, val).map(|_| None),
+        Statement::Expr(expr) => eval_expr(ctx, expr).map(|_| None),
+        Statement::Return(expr) => Err(Command::Return(eval_expr(ctx, expr)?)),
+        Statement::Break(expr) => Err(Command::Break(eval_expr(ctx, expr)?)),
         Statement::Continue => Err(Command::Continue),
-        Statement::ImplicitReturn(expr) => {
-            eval_expr(scope, expr).map(|return_val| Some(return_val))
-        }
+        Statement::ImplicitReturn(expr) => eval_expr(ctx, expr).map(Some),
+        Statement::Comment(_) => Ok(None),
     }
 }
 
-pub fn eval_expr(scope: &mut Scope, expr: &Expression) -> Result<VariableValue, Command> {
+pub fn eval_expr(ctx: &mut Context, expr: &Expression) -> Result<VariableValue, Command> {
+    ctx.consume_fuel().map_err(Command::Error)?;
     match expr {
         Expression::Block(stmnts) => {
-            enter_scope(scope);
-            let result = exec_stmnts(scope, stmnts).map(|v| v.unwrap_or(VariableValue::Unit));
-            exit_scope(scope);
+            enter_scope(&mut ctx.scope);
+            let result = exec_stmnts(ctx, stmnts).map(|v| v.unwrap_or(VariableValue::Unit));
+            exit_scope(&mut ctx.scope);
             result
         }
 
         Expression::List(list) => Ok(VariableValue::List(
             list.iter()
-                .map(|v| eval_expr(scope, v))
+                .map(|v| eval_expr(ctx, v))
                 .collect::<Result<Vec<VariableValue>, Command>>()?,
         )),
         Expression::Object(fields) => Ok(VariableValue::Object(
             fields
                 .iter()
-                .map(|(key, v)| eval_expr(scope, v).map(|r| (key.clone(), r)))
-                .collect::<Result<HashMap<String, VariableValue>, Command>>()?,
+                .map(|(key, v)| eval_expr(ctx, v).map(|r| (key.clone(), r)))
+                .collect::<Result<IndexMap<String, VariableValue>, Command>>()?,
         )),
         Expression::Value(var) => Ok(var.clone()),
-        Expression::Reference(ref_expr) => get_var_cloned(scope, ref_expr),
-        Expression::ForLoop(var_name, iterator, body) => {
-            let iter = eval_expr(scope, iterator)?; //TODO: don't accept commands!
-            match iter {
-                VariableValue::List(list) => {
-                    define_var_by_val(scope, &var_name, VariableValue::Unit)?;
-                    let mut result = VariableValue::Unit;
-                    for val in list {
-                        assign_var_by_name(scope, &var_name, val)?;
-                        match eval_expr(scope, body) {
-                            Ok(_) => (),
-                            Err(cmd) => match cmd {
-                                Command::Break(v) => {
-                                    result = v;
-                                    break;
-                                }
-                                Command::Continue => continue,
-                                Command::Return(v) => return Err(Command::Return(v)),
-                                Command::Error(e) => return Err(Command::Error(e)),
-                            },
-                        }
-                    }
-                    Ok(result)
-                }
-                VariableValue::Object(object) => {
-                    define_var_by_val(scope, &var_name, VariableValue::Unit)?;
-                    let mut result = VariableValue::Unit;
-                    for (key, _) in object {
-                        assign_var_by_name(scope, &var_name, VariableValue::String(key))?;
-                        match eval_expr(scope, body) {
-                            Ok(_) => (),
-                            Err(cmd) => match cmd {
-                                Command::Break(v) => {
-                                    result = v;
-                                    break;
-                                }
-                                Command::Continue => continue,
-                                Command::Return(v) => return Err(Command::Return(v)),
-                                Command::Error(e) => return Err(Command::Error(e)),
-                            },
-                        }
-                    }
-                    Ok(result)
-                }
-
-                _ => Err(Command::Error(
-                    format!("cannot iterate over {}", iter).into(),
-                )),
+        Expression::Reference(ref_expr) => get_var_cloned(ctx, ref_expr),
+        Expression::ForLoop(binding, iterator, body) => {
+            if let Some(bounds) = eval_loop_range(ctx, iterator)? {
+                let items = bounds.map(|n| VariableValue::Int(n as i64));
+                return run_loop(ctx, binding, items, body);
             }
+            let iter = eval_expr(ctx, iterator)?.thaw(); //TODO: don't accept commands!
+            let items = match iter {
+                VariableValue::List(list) => list,
+                VariableValue::Bytes(bytes) => bytes
+                    .into_iter()
+                    .map(|b| VariableValue::Int(b as i64))
+                    .collect(),
+                VariableValue::String(s) => s
+                    .chars()
+                    .map(|c| VariableValue::String(c.to_string()))
+                    .collect(),
+                VariableValue::Object(object) => object
+                    .into_iter()
+                    .map(|(key, val)| VariableValue::List(vec![VariableValue::String(key), val]))
+                    .collect(),
+                _ => {
+                    return Err(Command::Error(
+                        format!("cannot iterate over {}", iter).into(),
+                    ))
+                }
+            };
+            run_loop(ctx, binding, items, body)
         }
         Expression::WhileLoop(condition_expr, body) => loop {
-            if let VariableValue::Boolean(condition) = eval_expr(scope, condition_expr)? {
-                if !condition {
-                    break Ok(VariableValue::Unit);
+            if !eval_condition(ctx, condition_expr)? {
+                break Ok(VariableValue::Unit);
+            }
+            match eval_expr(ctx, body) {
+                Ok(_) => (),
+                Err(cmd) => match cmd {
+                    Command::Break(val) => break Ok(val),
+                    Command::Continue => continue,
+                    Command::Return(v) => return Err(Command::Return(v)),
+                    Command::Error(e) => return Err(Command::Error(e)),
+                },
+            }
+        },
+        Expression::Let(var, value, body) => {
+            let val = eval_expr(ctx, value)?;
+            enter_scope(&mut ctx.scope);
+            let result = define_var_by_val(ctx, var, val).and_then(|_| eval_expr(ctx, body));
+            exit_scope(&mut ctx.scope);
+            result
+        }
+        Expression::Match(scrutinee, arms, span) => {
+            let val = eval_expr(ctx, scrutinee)?;
+            for (pattern, body) in arms {
+                let is_match = match pattern {
+                    Pattern::Wildcard => true,
+                    Pattern::Value(pattern_val) => matches!(
+                        VariableValue::equals(val.clone(), pattern_val.clone()),
+                        Ok(VariableValue::Boolean(true))
+                    ),
+                };
+                if is_match {
+                    return eval_expr(ctx, body);
                 }
-                match eval_expr(scope, body) {
-                    Ok(_) => (),
-                    Err(cmd) => match cmd {
-                        Command::Break(val) => break Ok(val),
-                        Command::Continue => continue,
-                        Command::Return(v) => return Err(Command::Return(v)),
-                        Command::Error(e) => return Err(Command::Error(e)),
-                    },
+            }
+            Err(Command::Error(format!("no match arm matched {}", val).into()).at(*span))
+        }
+        Expression::FunctionCall(func_expr, params, named_params, span) => {
+            if let Expression::Reference(ref_expr) = func_expr.as_ref() {
+                if let ReferenceExpr::Variable(name, _) = ref_expr.as_ref() {
+                    if MUTATING_BUILTIN_NAMES.contains(&name.as_str()) {
+                        return if named_params.is_empty() {
+                            exec_mutating_builtin(ctx, name, params)
+                        } else {
+                            Err(Command::Error(
+                                format!("{} takes no named arguments", name).into(),
+                            ))
+                        }
+                        .map_err(|cmd| cmd.at(*span));
+                    }
                 }
             }
-        },
-        Expression::FunctionCall(func_expr, params) => {
             let p = params
                 .iter()
-                .map(|v| eval_expr(scope, v))
+                .map(|v| eval_expr(ctx, v))
                 .collect::<Result<Vec<VariableValue>, Command>>()?;
-            eval_expr(scope, func_expr)?.call(scope, p)
+            let function = eval_expr(ctx, func_expr)?;
+            if named_params.is_empty() {
+                return function.call(ctx, p).map_err(|cmd| cmd.at(*span));
+            }
+            let named = named_params
+                .iter()
+                .map(|(name, v)| eval_expr(ctx, v).map(|val| (name.to_string(), val)))
+                .collect::<Result<Vec<(String, VariableValue)>, Command>>()?;
+            match function {
+                VariableValue::Function(_, body) => match *body {
+                    Expression::BuiltinFunctionCall(name, target, _) => {
+                        exec_builtin_named(ctx, &name, &target, &p, &named)
+                    }
+                    _ => Err(Command::Error(
+                        "only builtin functions take named arguments".into(),
+                    )),
+                },
+                _ => function.call(ctx, p),
+            }
+            .map_err(|cmd| cmd.at(*span))
         }
         Expression::BuiltinFunctionCall(name, target, params) => {
-            exec_builtin(scope, name, target, params)
+            exec_builtin(ctx, name, target, params)
         }
-        Expression::BinaryOperator(a, b, op) => {
-            evaluate_binary_op(eval_expr(scope, a)?, eval_expr(scope, b)?, *op)
-                .map_err(|v| Command::Error(v))
+        Expression::BinaryOperator(a, b, op, span) => {
+            evaluate_binary_op(eval_expr(ctx, a)?, eval_expr(ctx, b)?, *op)
+                .map_err(|v| Command::Error(v.at(*span)))
+        }
+        Expression::UnaryOperator(a, op, span) => {
+            evaluate_unary_op(eval_expr(ctx, a)?, *op).map_err(|cmd| cmd.at(*span))
         }
-        Expression::UnaryOperator(a, op) => evaluate_unary_op(eval_expr(scope, a)?, *op),
         Expression::IfElse(cond_expr, if_expr, else_expr) => {
-            if let VariableValue::Boolean(cond) = eval_expr(scope, cond_expr)? {
-                if cond {
-                    eval_expr(scope, if_expr)
-                } else {
-                    if let Some(else_e) = else_expr {
-                        eval_expr(scope, else_e)
-                    } else {
-                        Ok(VariableValue::Unit)
-                    }
-                }
+            if eval_condition(ctx, cond_expr)? {
+                eval_expr(ctx, if_expr)
+            } else if let Some(else_e) = else_expr {
+                eval_expr(ctx, else_e)
             } else {
-                Err(Command::Error("condition is not a boolean".into()))
+                Ok(VariableValue::Unit)
+            }
+        }
+    }
+}
+
+/// Runs the body of a for loop once for every item, bound to `binding`.
+fn run_loop(
+    ctx: &mut Context,
+    binding: &Binding,
+    items: impl IntoIterator<Item = VariableValue>,
+    body: &Expression,
+) -> Result<VariableValue, Command> {
+    match binding {
+        Binding::Variable(var_name) => {
+            define_var_by_val(ctx, var_name, VariableValue::Unit)?;
+        }
+        Binding::List(var_names) => {
+            for var_name in var_names {
+                define_var_by_val(ctx, var_name, VariableValue::Unit)?;
             }
         }
     }
+    let mut result = VariableValue::Unit;
+    for val in items {
+        assign_binding(ctx, binding, val)?;
+        match eval_expr(ctx, body) {
+            Ok(_) => (),
+            Err(cmd) => match cmd {
+                Command::Break(v) => {
+                    result = v;
+                    break;
+                }
+                Command::Continue => continue,
+                Command::Return(v) => return Err(Command::Return(v)),
+                Command::Error(e) => return Err(Command::Error(e)),
+            },
+        }
+    }
+    Ok(result)
+}
+
+/// The bounds of a loop over `a..b` or `a..=b`, which is walked without building the list, so
+/// a loop can stop early in a huge range.
+fn eval_loop_range(
+    ctx: &mut Context,
+    iterator: &Expression,
+) -> Result<Option<std::ops::Range<i128>>, Command> {
+    match iterator {
+        Expression::BinaryOperator(
+            a,
+            b,
+            op @ (Operator::Range | Operator::RangeInclusive),
+            span,
+        ) => {
+            let (a, b) = (eval_expr(ctx, a)?, eval_expr(ctx, b)?);
+            VariableValue::range_bounds(a, b, *op == Operator::RangeInclusive)
+                .map(Some)
+                .map_err(|e| Command::Error(e.at(*span)))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Conditions must be booleans unless the context lets any value be tested for truthiness.
+fn eval_condition(ctx: &mut Context, expr: &Expression) -> Result<bool, Command> {
+    match eval_expr(ctx, expr)?.thaw() {
+        VariableValue::Boolean(cond) => Ok(cond),
+        value if ctx.truthy_conditions() => Ok(value.is_truthy()),
+        _ => Err(Command::Error("condition is not a boolean".into())),
+    }
 }
 
 pub fn define_var(
-    scope: &mut Scope,
+    ctx: &mut Context,
+    var: &str,
+    expr: &Expression,
+) -> Result<VariableValue, Command> {
+    let val = eval_expr(ctx, expr)?;
+    define_var_by_val(ctx, var, val)
+}
+
+pub fn define_const(
+    ctx: &mut Context,
     var: &str,
     expr: &Expression,
 ) -> Result<VariableValue, Command> {
-    let val = eval_expr(scope, expr)?;
-    define_var_by_val(scope, var, val)
+    let val = eval_expr(ctx, expr)?;
+    define_const_in_scope(&mut ctx.scope, var, val).map(|_| VariableValue::Unit)
 }
 
 pub fn define_var_by_val(
-    scope: &mut Scope,
+    ctx: &mut Context,
     var: &str,
     val: VariableValue,
 ) -> Result<VariableValue, Command> {
-    define_var_in_scope(scope, var, val).map(|_| VariableValue::Unit)
+    define_var_in_scope(&mut ctx.scope, var, val).map(|_| VariableValue::Unit)
 }
 
 pub fn assign_var_by_name(
-    scope: &mut Scope,
+    ctx: &mut Context,
     var: &str,
     val: VariableValue,
 ) -> Result<VariableValue, Command> {
-    assign_var_in_scope(scope, var, val).map(|_| VariableValue::Unit)
+    assign_var_in_scope(&mut ctx.scope, var, val).map(|_| VariableValue::Unit)
+}
+
+pub fn assign_binding(
+    ctx: &mut Context,
+    binding: &Binding,
+    val: VariableValue,
+) -> Result<VariableValue, Command> {
+    match (binding, val) {
+        (Binding::Variable(var_name), val) => assign_var_by_name(ctx, var_name, val),
+        (Binding::List(var_names), VariableValue::List(list)) => {
+            if var_names.len() != list.len() {
+                return Err(Command::Error(
+                    format!(
+                        "cannot destructure a list of length {} into {} variables",
+                        list.len(),
+                        var_names.len()
+                    )
+                    .into(),
+                ));
+            }
+            for (var_name, val) in var_names.iter().zip(list) {
+                assign_var_by_name(ctx, var_name, val)?;
+            }
+            Ok(VariableValue::Unit)
+        }
+        (Binding::List(_), val) => {
+            Err(Command::Error(format!("cannot destructure {}", val).into()))
+        }
+    }
+}
+
+/// Evaluates an index. Floats are rejected unless the context truncates them.
+fn eval_index(
+    ctx: &mut Context,
+    index_expr: &Expression,
+    span: Span,
+) -> Result<VariableValue, Command> {
+    match (eval_expr(ctx, index_expr)?, ctx.index_policy()) {
+        (VariableValue::Float(f), IndexPolicy::Truncate) if f.is_finite() => {
+            Ok(VariableValue::Int(f.trunc() as i64))
+        }
+        (VariableValue::Float(f), _) => {
+            Err(Command::Error(format!("index must be an integer, got {}", f).into()).at(span))
+        }
+        (index, _) => Ok(index),
+    }
+}
+
+/// Turns an index into a position in a sequence of length `len`. Negative indices count from
+/// the end, so `-1` is the last element.
+pub(crate) fn resolve_index(index: i64, len: usize) -> Result<usize, Command> {
+    let position = if index < 0 { index + len as i64 } else { index };
+    if (0..len as i64).contains(&position) {
+        Ok(position as usize)
+    } else if index < 0 {
+        Err(Command::Error(
+            format!(
+                "Index {} (position {}) is out of bounds for length {}",
+                index, position, len
+            )
+            .into(),
+        ))
+    } else {
+        Err(Command::Error(
+            format!("Index {} is out of bounds for length {}", index, len).into(),
+        ))
+    }
+}
+
+fn missing_field(field: &str) -> Command {
+    Command::Error(format!("Object has no field '{}'", field).into())
+}
+
+/// Looks inside a frozen value, so that references can reach the values within it. Those are
+/// only frozen themselves if the value was deep-frozen.
+fn see_through_frozen(value: &mut VariableValue) -> &mut VariableValue {
+    match value {
+        VariableValue::Frozen(inner) => inner,
+        other => other,
+    }
+}
+
+/// Fails if a value that is about to be changed in place is frozen.
+pub(crate) fn ensure_not_frozen(value: &VariableValue) -> Result<(), Command> {
+    match value {
+        VariableValue::Frozen(inner) => Err(Command::Error(
+            format!("Cannot change a frozen {}", inner.get_type()).into(),
+        )),
+        _ => Ok(()),
+    }
 }
 
 pub fn get_var<'a>(
-    scope: &'a mut Scope,
+    ctx: &'a mut Context,
     var_expr: &ReferenceExpr,
 ) -> Result<&'a mut VariableValue, Command> {
     match var_expr {
-        ReferenceExpr::Variable(ref var) => get_var_from_scope(scope, var),
-        ReferenceExpr::Index(list_expr, index_expr) => {
-            let index = eval_expr(scope, index_expr)?;
+        ReferenceExpr::Variable(ref var, _) => get_var_from_scope(&mut ctx.scope, var),
+        ReferenceExpr::Index(list_expr, index_expr, span) => {
+            let index = eval_index(ctx, index_expr, *span)?;
             if let Expression::Reference(ref_expr) = list_expr {
-                let li = get_var(scope, ref_expr)?;
-                match (li, index) {
-                    (VariableValue::List(li_vec), VariableValue::Number(i)) => li_vec
-                        .get_mut(i as usize)
-                        .ok_or(Command::Error("Index is out of bounds".into())),
+                let li = get_var(ctx, ref_expr)?;
+                match (see_through_frozen(li), index) {
+                    (VariableValue::List(li_vec), VariableValue::Int(i)) => {
+                        resolve_index(i, li_vec.len()).map(|i| &mut li_vec[i])
+                    }
                     (VariableValue::Object(obj_map), VariableValue::String(key)) => obj_map
                         .get_mut(&key)
                         .ok_or(Command::Error("Index is out of bounds".into())),
-                    (_, _) => Err(Command::Error("Variable is not a list".into())),
+                    (a, b) => Err(Command::Error(
+                        format!("{} cannot be indexed by {}", a, b).into(),
+                    )),
                 }
             } else {
                 Err(Command::Error("Variable is not a ref".into()))
             }
         }
-        ReferenceExpr::Object(object_expr, index_expr) => {
+        ReferenceExpr::Object(object_expr, index_expr, _) => {
             if let Expression::Reference(ref_expr) = object_expr {
-                let object = get_var(scope, ref_expr)?;
-                if let VariableValue::Object(ref mut obj) = object {
-                    obj.get_mut(index_expr)
-                        .ok_or(Command::Error("Not a field of the object".into()))
+                let object = get_var(ctx, ref_expr)?;
+                if let VariableValue::Object(ref mut obj) = see_through_frozen(object) {
+                    obj.get_mut(index_expr).ok_or(missing_field(index_expr))
                 } else {
                     Err(Command::Error("Variable is not an object".into()))
                 }
@@ -241,32 +464,40 @@ pub fn get_var<'a>(
             }
         }
     }
+    .map_err(|cmd| cmd.at(var_expr.span()))
 }
 pub fn get_var_cloned(
-    scope: &mut Scope,
+    ctx: &mut Context,
     var_expr: &ReferenceExpr,
 ) -> Result<VariableValue, Command> {
     match var_expr {
-        ReferenceExpr::Variable(ref var) => {
-            get_var_from_scope_cloned(scope, var).or_else(|e| is_builtin(var, None).ok_or(e))
+        ReferenceExpr::Variable(ref var, _) => {
+            get_var_from_scope_cloned(&ctx.scope, var).or_else(|e| {
+                is_builtin(var, None)
+                    .or_else(|| ctx.is_native_fn(var).then(|| native_fn_value(var)))
+                    .ok_or(e)
+            })
         }
-        ReferenceExpr::Index(list_expr, index_expr) => {
-            let index = eval_expr(scope, index_expr)?;
+        ReferenceExpr::Index(list_expr, index_expr, span) => {
+            let index = eval_index(ctx, index_expr, *span)?;
             let li = if let Expression::Reference(ref_expr) = list_expr {
-                get_var_cloned(scope, ref_expr)?
+                get_var_cloned(ctx, ref_expr)?
             } else {
-                eval_expr(scope, list_expr)?
-            };
+                eval_expr(ctx, list_expr)?
+            }
+            .thaw();
             match (li, index) {
-                (VariableValue::List(li_vec), VariableValue::Number(i)) => li_vec
-                    .get(i as usize)
-                    .cloned()
-                    .ok_or(Command::Error("Index out of bounds".into())),
-                (VariableValue::String(stri), VariableValue::Number(i)) => stri
-                    .chars()
-                    .nth(i as usize)
-                    .map(|v| VariableValue::String(v.to_string()))
-                    .ok_or(Command::Error("Index out of bounds".into())),
+                (VariableValue::List(li_vec), VariableValue::Int(i)) => {
+                    resolve_index(i, li_vec.len()).map(|i| li_vec[i].clone())
+                }
+                (VariableValue::String(stri), VariableValue::Int(i)) => {
+                    let chars: Vec<char> = stri.chars().collect();
+                    resolve_index(i, chars.len())
+                        .map(|i| VariableValue::String(chars[i].to_string()))
+                }
+                (VariableValue::Bytes(bytes), VariableValue::Int(i)) => {
+                    resolve_index(i, bytes.len()).map(|i| VariableValue::Int(bytes[i] as i64))
+                }
                 (VariableValue::Object(obj_map), VariableValue::String(key)) => obj_map
                     .get(&key)
                     .cloned()
@@ -276,19 +507,20 @@ pub fn get_var_cloned(
                 )),
             }
         }
-        ReferenceExpr::Object(object_expr, index_expr) => {
+        ReferenceExpr::Object(object_expr, index_expr, _) => {
             let object = if let Expression::Reference(ref_expr) = object_expr {
-                get_var_cloned(scope, ref_expr)?
+                get_var_cloned(ctx, ref_expr)?
             } else {
-                eval_expr(scope, object_expr)?
+                eval_expr(ctx, object_expr)?
             };
-            if let VariableValue::Object(ref obj) = object {
+            // the receiver stays frozen, so that e.g. `is_frozen` sees it as it is
+            if let VariableValue::Object(obj) = object.thawed() {
                 if let Some(val) = obj.get(index_expr) {
                     return Ok(val.clone());
                 }
             }
             is_builtin(&index_expr, Some(&object)).ok_or(Command::Error(
-                if let VariableValue::Object(_) = object {
+                if let VariableValue::Object(_) = object.thawed() {
                     format!("Identifier '{}' is not a field of the object", index_expr)
                 } else {
                     format!("Variable '{}' is not an object", object)
@@ -297,25 +529,36 @@ pub fn get_var_cloned(
             ))
         }
     }
+    .map_err(|cmd| cmd.at(var_expr.span()))
 }
 
 pub fn assign_var(
-    scope: &mut Scope,
+    ctx: &mut Context,
     var_expr: &ReferenceExpr,
     expr: &Expression,
 ) -> Result<VariableValue, Command> {
-    let val = eval_expr(scope, expr)?;
+    let val = eval_expr(ctx, expr)?;
     match var_expr {
-        ReferenceExpr::Variable(ref var) => assign_var_by_name(scope, var, val),
-        ReferenceExpr::Index(list_expr, index_expr) => {
-            let index = eval_expr(scope, index_expr)?;
+        ReferenceExpr::Variable(ref var, _) => assign_var_by_name(ctx, var, val),
+        ReferenceExpr::Index(list_expr, index_expr, span) => {
+            let index = eval_index(ctx, index_expr, *span)?;
             if let Expression::Reference(ref_expr) = list_expr {
-                let list = get_var(scope, ref_expr)?;
+                let list = get_var(ctx, ref_expr)?;
                 match (list, index) {
-                    (VariableValue::List(li_vec), VariableValue::Number(i)) => li_vec
-                        .get_mut(i as usize)
-                        .map(|v| *v = val)
-                        .ok_or(Command::Error("Index out of bounds".into())),
+                    (frozen @ VariableValue::Frozen(_), _) => ensure_not_frozen(frozen),
+                    (VariableValue::List(li_vec), VariableValue::Int(i)) => {
+                        resolve_index(i, li_vec.len()).map(|i| li_vec[i] = val)
+                    }
+                    (VariableValue::Bytes(bytes), VariableValue::Int(i)) => {
+                        let byte = match val {
+                            VariableValue::Int(n) => u8::try_from(n).ok(),
+                            _ => None,
+                        }
+                        .ok_or(Command::Error(
+                            format!("A byte must be an integer from 0 to 255, got {}", val).into(),
+                        ))?;
+                        resolve_index(i, bytes.len()).map(|i| bytes[i] = byte)
+                    }
                     (VariableValue::Object(obj_map), VariableValue::String(key)) => obj_map
                         .get_mut(&key)
                         .map(|v| *v = val)
@@ -329,21 +572,22 @@ pub fn assign_var(
                 Err(Command::Error("Variable is not reference".into()))
             }
         }
-        ReferenceExpr::Object(object_expr, index_expr) => {
+        ReferenceExpr::Object(object_expr, index_expr, _) => {
             if let Expression::Reference(ref_expr) = object_expr {
-                let object = get_var(scope, ref_expr)?;
-                if let VariableValue::Object(ref mut scope) = object {
-                    scope
+                let object = get_var(ctx, ref_expr)?;
+                match object {
+                    VariableValue::Object(scope) => scope
                         .get_mut(index_expr)
                         .map(|v| *v = val)
-                        .ok_or(Command::Error("Not a field of the object".into()))
-                        .map(|_| VariableValue::Unit)
-                } else {
-                    Err(Command::Error("Variable is not an object".into()))
+                        .ok_or(missing_field(index_expr)),
+                    frozen @ VariableValue::Frozen(_) => ensure_not_frozen(frozen),
+                    _ => Err(Command::Error("Variable is not an object".into())),
                 }
+                .map(|_| VariableValue::Unit)
             } else {
                 Err(Command::Error("Variable is not a reference".into()))
             }
         }
     }
+    .map_err(|cmd| cmd.at(var_expr.span()))
 }
@@ -0,0 +1,367 @@
+use crate::*;
+
+#[derive(Debug, Clone)]
+pub struct FormatOptions {
+    pub indent_width: usize,
+    pub use_tabs: bool,
+    pub max_line_length: usize,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions {
+            indent_width: 4,
+            use_tabs: false,
+            max_line_length: 100,
+        }
+    }
+}
+
+pub fn format_program(program: &str, options: &FormatOptions) -> Result<String, SyntaxError> {
+    let tokens = tokenize_with_comments(program)?;
+    let reduced = reduce_brackets_and_parenths(&tokens)?;
+    let statements = get_statements(&reduced)?;
+
+    let formatter = Formatter { options };
+    let mut s = formatter.fmt_stmnts(&statements, 0);
+    s.push('\n');
+    // comments inside lists, objects, calls and parameters have no place in the syntax tree, so
+    // refuse to format rather than drop one
+    let formatted = tokenize_with_comments(&s)?;
+    let mut kept = formatted.iter().filter_map(comment_text);
+    if let Some(dropped) = tokens
+        .iter()
+        .find(|t| comment_text(t).is_some_and(|text| !kept.any(|k| k == text)))
+    {
+        return Err(SyntaxError::from(
+            "cannot format a comment inside a list, object, call or parameter list",
+        )
+        .at(dropped.span));
+    }
+    Ok(s)
+}
+
+fn comment_text(token: &Spanned<Token>) -> Option<&str> {
+    match &token.node {
+        Token::Comment(comment) => Some(&comment.text),
+        _ => None,
+    }
+}
+
+struct Formatter<'a> {
+    options: &'a FormatOptions,
+}
+
+impl Formatter<'_> {
+    fn indent(&self, depth: usize) -> String {
+        if self.options.use_tabs {
+            "\t".repeat(depth)
+        } else {
+            " ".repeat(depth * self.options.indent_width)
+        }
+    }
+
+    fn fits(&self, depth: usize, s: &str) -> bool {
+        !s.contains('\n')
+            && self.indent(depth).chars().count() + s.chars().count()
+                <= self.options.max_line_length
+    }
+
+    /// Trailing comments stay at the end of the line of the statement before them.
+    fn fmt_stmnts(&self, stmnts: &[Statement], depth: usize) -> String {
+        let mut lines: Vec<String> = Vec::new();
+        for stmnt in stmnts {
+            match (stmnt, lines.last_mut()) {
+                (Statement::Comment(comment), Some(line)) if comment.trailing => {
+                    line.push_str(&format!(" {}", fmt_comment(comment)))
+                }
+                _ => lines.push(format!(
+                    "{}{}",
+                    self.indent(depth),
+                    self.fmt_stmnt(stmnt, depth)
+                )),
+            }
+        }
+        lines.join("\n")
+    }
+
+    fn fmt_stmnt(&self, stmnt: &Statement, depth: usize) -> String {
+        match stmnt {
+            Statement::VariableDefinition(var, Expression::Value(VariableValue::None)) => {
+                format!("let {};", var)
+            }
+            Statement::VariableDefinition(var, expr) => {
+                format!("let {} = {};", var, self.fmt_expr(expr, depth))
+            }
+            Statement::ConstDefinition(var, expr) => {
+                format!("const {} = {};", var, self.fmt_expr(expr, depth))
+            }
+            Statement::VariableAssignment(var, expr) => format!(
+                "{} = {};",
+                self.fmt_reference(var, depth),
+                self.fmt_expr(expr, depth)
+            ),
+            Statement::Expr(expr) => format!("{};", self.fmt_expr(expr, depth)),
+            Statement::Return(Expression::Value(VariableValue::Unit)) => "return;".to_string(),
+            Statement::Return(expr) => format!("return {};", self.fmt_expr(expr, depth)),
+            Statement::Break(Expression::Value(VariableValue::Unit)) => "break;".to_string(),
+            Statement::Break(expr) => format!("break {};", self.fmt_expr(expr, depth)),
+            Statement::Continue => "continue;".to_string(),
+            Statement::ImplicitReturn(expr) => self.fmt_expr(expr, depth),
+            Statement::Comment(comment) => fmt_comment(comment),
+        }
+    }
+
+    fn fmt_expr(&self, expr: &Expression, depth: usize) -> String {
+        match expr {
+            Expression::Value(val) => self.fmt_value(val, depth),
+            Expression::List(list) => {
+                let items: Vec<String> = list.iter().map(|e| self.fmt_expr(e, depth + 1)).collect();
+                self.fmt_sequence("[", &items, "]", depth)
+            }
+            Expression::Object(fields) => {
+                let items: Vec<String> = fields
+                    .iter()
+                    .map(|(key, e)| format!("{}: {}", key, self.fmt_expr(e, depth + 1)))
+                    .collect();
+                self.fmt_sequence("{", &items, "}", depth)
+            }
+            Expression::Reference(ref_expr) => self.fmt_reference(ref_expr, depth),
+            Expression::BinaryOperator(a, b, op, _) => {
+                // ranges are written without spaces, like `0..10`
+                let op_str = match op {
+                    Operator::Range | Operator::RangeInclusive => Token::Operator(*op).to_string(),
+                    _ => format!(" {} ", Token::Operator(*op)),
+                };
+                format!(
+                    "{}{}{}",
+                    self.fmt_operator_operand(a, op.precedence(), true, depth),
+                    op_str,
+                    self.fmt_operator_operand(b, op.precedence(), false, depth)
+                )
+            }
+            Expression::UnaryOperator(a, op, _) => format!(
+                "{}{}",
+                Token::Operator(*op),
+                self.fmt_operator_operand(a, op.precedence(), false, depth)
+            ),
+            Expression::Block(stmnts) => self.fmt_block(stmnts, depth),
+            Expression::FunctionCall(func, params, named_params, _) => {
+                let items: Vec<String> = params
+                    .iter()
+                    .map(|e| self.fmt_expr(e, depth + 1))
+                    .chain(
+                        named_params
+                            .iter()
+                            .map(|(name, e)| format!("{} = {}", name, self.fmt_expr(e, depth + 1))),
+                    )
+                    .collect();
+                format!(
+                    "{}{}",
+                    self.fmt_operand(func, depth),
+                    self.fmt_sequence("(", &items, ")", depth)
+                )
+            }
+            Expression::BuiltinFunctionCall(name, _, _) => name.to_string(),
+            Expression::IfElse(cond, if_expr, else_expr) => {
+                let mut s = format!(
+                    "if {} {}",
+                    self.fmt_expr(cond, depth),
+                    self.fmt_expr(if_expr, depth)
+                );
+                if let Some(else_e) = else_expr {
+                    s.push_str(" else ");
+                    s.push_str(&self.fmt_expr(else_e, depth));
+                }
+                s
+            }
+            Expression::ForLoop(binding, iterator, body) => {
+                let binding = match binding {
+                    Binding::Variable(var) => var.to_string(),
+                    Binding::List(vars) => format!("[{}]", vars.join(", ")),
+                };
+                format!(
+                    "for {} in {} {}",
+                    binding,
+                    self.fmt_expr(iterator, depth),
+                    self.fmt_expr(body, depth)
+                )
+            }
+            Expression::WhileLoop(cond, body) => format!(
+                "while {} {}",
+                self.fmt_expr(cond, depth),
+                self.fmt_expr(body, depth)
+            ),
+            Expression::Let(var, value, body) => format!(
+                "let {} = {} in {}",
+                var,
+                self.fmt_expr(value, depth),
+                self.fmt_expr(body, depth)
+            ),
+            Expression::Match(scrutinee, arms, _) => {
+                let mut s = format!("match {} {{\n", self.fmt_expr(scrutinee, depth));
+                for (pattern, body) in arms {
+                    let pattern = match pattern {
+                        Pattern::Value(val) => self.fmt_value(val, depth + 1),
+                        Pattern::Wildcard => "_".to_string(),
+                    };
+                    s.push_str(&format!(
+                        "{}{}: {},\n",
+                        self.indent(depth + 1),
+                        pattern,
+                        self.fmt_expr(body, depth + 1)
+                    ));
+                }
+                s.push_str(&self.indent(depth));
+                s.push('}');
+                s
+            }
+        }
+    }
+
+    fn fmt_reference(&self, ref_expr: &ReferenceExpr, depth: usize) -> String {
+        match ref_expr {
+            ReferenceExpr::Variable(var, _) => var.to_string(),
+            ReferenceExpr::Index(list, index, _) => format!(
+                "{}[{}]",
+                self.fmt_operand(list, depth),
+                self.fmt_expr(index, depth)
+            ),
+            ReferenceExpr::Object(object, field, _) => {
+                format!("{}.{}", self.fmt_operand(object, depth), field)
+            }
+        }
+    }
+
+    /// Operands of operators, calls and accesses are parenthesized unless they are atomic.
+    fn fmt_operand(&self, expr: &Expression, depth: usize) -> String {
+        let s = self.fmt_expr(expr, depth);
+        match expr {
+            Expression::Value(VariableValue::Function(_, _))
+            | Expression::BinaryOperator(_, _, _, _)
+            | Expression::UnaryOperator(_, _, _)
+            | Expression::IfElse(_, _, _)
+            | Expression::ForLoop(_, _, _)
+            | Expression::WhileLoop(_, _)
+            | Expression::Match(_, _, _)
+            | Expression::Let(_, _, _) => format!("({})", s),
+            _ => s,
+        }
+    }
+
+    /// Operands of an operator are only parenthesized if the parser would otherwise group them
+    /// differently. Operators of equal precedence group to the left, so only a left operand may
+    /// have the same precedence as its parent without parentheses. A prefix operator right after
+    /// another operator always applies to just the operand that follows it.
+    fn fmt_operator_operand(
+        &self,
+        expr: &Expression,
+        parent_precedence: u32,
+        allow_equal: bool,
+        depth: usize,
+    ) -> String {
+        let precedence = match expr {
+            Expression::UnaryOperator(..) if !allow_equal => return self.fmt_expr(expr, depth),
+            Expression::BinaryOperator(_, _, op, _) => op.precedence(),
+            Expression::UnaryOperator(_, op, _) => op.precedence(),
+            _ => return self.fmt_operand(expr, depth),
+        };
+        let s = self.fmt_expr(expr, depth);
+        if precedence > parent_precedence || (allow_equal && precedence == parent_precedence) {
+            s
+        } else {
+            format!("({})", s)
+        }
+    }
+
+    fn fmt_block(&self, stmnts: &[Statement], depth: usize) -> String {
+        if stmnts.is_empty() {
+            return "{}".to_string();
+        }
+        if let [Statement::ImplicitReturn(expr)] = stmnts {
+            let inline = format!("{{ {} }}", self.fmt_expr(expr, depth));
+            if self.fits(depth, &inline) {
+                return inline;
+            }
+        }
+        format!(
+            "{{\n{}\n{}}}",
+            self.fmt_stmnts(stmnts, depth + 1),
+            self.indent(depth)
+        )
+    }
+
+    fn fmt_sequence(&self, open: &str, items: &[String], close: &str, depth: usize) -> String {
+        let inline = format!("{}{}{}", open, items.join(", "), close);
+        if items.is_empty() || self.fits(depth, &inline) {
+            return inline;
+        }
+        let mut s = open.to_string();
+        for (i, item) in items.iter().enumerate() {
+            s.push('\n');
+            s.push_str(&self.indent(depth + 1));
+            s.push_str(item);
+            if i + 1 < items.len() {
+                s.push(',');
+            }
+        }
+        s.push('\n');
+        s.push_str(&self.indent(depth));
+        s.push_str(close);
+        s
+    }
+
+    fn fmt_value(&self, val: &VariableValue, depth: usize) -> String {
+        match val {
+            VariableValue::String(s) => format!("\"{}\"", escape_string(s)),
+            VariableValue::Function(args, body) => {
+                let params: Vec<String> = args
+                    .iter()
+                    .map(|arg| match &arg.default {
+                        _ if arg.rest => format!("...{}", arg.name),
+                        Some(default) => format!("{}: {}", arg.name, self.fmt_expr(default, depth)),
+                        None => arg.name.to_string(),
+                    })
+                    .collect();
+                format!("|{}| {}", params.join(", "), self.fmt_expr(body, depth))
+            }
+            VariableValue::List(list) => {
+                let items: Vec<String> =
+                    list.iter().map(|v| self.fmt_value(v, depth + 1)).collect();
+                self.fmt_sequence("[", &items, "]", depth)
+            }
+            VariableValue::Object(fields) => {
+                let items: Vec<String> = fields
+                    .iter()
+                    .map(|(key, v)| format!("{}: {}", key, self.fmt_value(v, depth + 1)))
+                    .collect();
+                self.fmt_sequence("{", &items, "}", depth)
+            }
+            _ => val.to_string(),
+        }
+    }
+}
+
+fn fmt_comment(comment: &Comment) -> String {
+    if comment.text.is_empty() {
+        "#".to_string()
+    } else {
+        format!("# {}", comment.text)
+    }
+}
+
+fn escape_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            '\0' => escaped.push_str("\\0"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,77 @@
+use std::ops::Range;
+
+use crate::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenClass {
+    Keyword,
+    Identifier,
+    Number,
+    String,
+    Operator,
+    Comment,
+    Punctuation,
+}
+
+/// A classified piece of source text. The span is a byte range into the source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HighlightToken {
+    pub class: TokenClass,
+    pub span: Range<usize>,
+}
+
+/// Splits `source` into classified tokens. The tokens are those of the tokenizer, so
+/// highlighting agrees with how the program is parsed; pieces the tokenizer rejects are still
+/// classified so that incomplete programs can be highlighted.
+pub fn classify_tokens(source: &str) -> Vec<HighlightToken> {
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+        .collect();
+    let offset = |span: Span| {
+        let line = &source[line_starts[span.line - 1]..];
+        line_starts[span.line - 1]
+            + line
+                .char_indices()
+                .nth(span.column - 1)
+                .map_or(line.len(), |(i, _)| i)
+    };
+
+    let tokens = tokenize_leniently(source);
+    let starts: Vec<usize> = tokens.iter().map(|t| offset(t.span)).collect();
+    tokens
+        .iter()
+        .enumerate()
+        .map(|(i, token)| {
+            // a token extends up to the next one, except for the whitespace in between
+            let start = starts[i];
+            let end = starts.get(i + 1).copied().unwrap_or(source.len());
+            HighlightToken {
+                class: classify(&token.node),
+                span: start..start + source[start..end].trim_end().len(),
+            }
+        })
+        .collect()
+}
+
+fn classify(token: &Result<Token, CharToken>) -> TokenClass {
+    match token {
+        Ok(Token::Keyword(_)) => TokenClass::Keyword,
+        Ok(Token::Value(VariableValue::Int(_) | VariableValue::Float(_))) => TokenClass::Number,
+        Ok(Token::Value(VariableValue::String(_))) => TokenClass::String,
+        Ok(Token::Value(_)) => TokenClass::Keyword,
+        Ok(Token::Identifier(_)) => TokenClass::Identifier,
+        Ok(Token::Comment(_)) => TokenClass::Comment,
+        Ok(
+            Token::Operator(_)
+            | Token::OperatorAssign(_)
+            | Token::Assign
+            | Token::Ampersand
+            | Token::VerticalBar,
+        ) => TokenClass::Operator,
+        Ok(_) => TokenClass::Punctuation,
+        Err(CharToken::String(_) | CharToken::CharLiteral(_)) => TokenClass::String,
+        Err(CharToken::Identifier(_)) => TokenClass::Number,
+        Err(CharToken::Comment(_)) => TokenClass::Comment,
+        Err(CharToken::Char(_)) => TokenClass::Punctuation,
+    }
+}
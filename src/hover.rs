@@ -0,0 +1,89 @@
+use crate::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HoverKind {
+    Variable,
+    Function,
+    Builtin,
+}
+
+#[derive(Debug, Clone)]
+pub struct HoverInfo {
+    pub name: String,
+    pub kind: HoverKind,
+    /// The value of the variable if its definition is a constant expression.
+    pub value: Option<VariableValue>,
+}
+
+/// Describes the identifier at byte offset `offset` of `program`, if it refers to a variable or
+/// builtin that is in scope there.
+pub fn hover(program: &str, offset: usize) -> Option<HoverInfo> {
+    let token = classify_tokens(program)
+        .into_iter()
+        .find(|t| t.class == TokenClass::Identifier && t.span.contains(&offset))?;
+    let name = program[token.span.clone()].to_string();
+
+    let definition = defined_variables(program, token.span.start)
+        .into_iter()
+        .rev()
+        .find(|definition| definition.name == name);
+    match definition {
+        Some(definition) => {
+            let initializer = definition
+                .initializer
+                .and_then(|span| parse_expr(&program[span]));
+            let kind = match initializer {
+                Some(Expression::Value(VariableValue::Function(_, _))) => HoverKind::Function,
+                _ => HoverKind::Variable,
+            };
+            let value = initializer
+                .as_ref()
+                .and_then(fold_constant)
+                .filter(|v| !matches!(v, VariableValue::Function(_, _)));
+            Some(HoverInfo { name, kind, value })
+        }
+        None if name == "cwd" => Some(HoverInfo {
+            name,
+            kind: HoverKind::Variable,
+            value: None,
+        }),
+        None if BUILTIN_NAMES.contains(&name.as_str())
+            || MUTATING_BUILTIN_NAMES.contains(&name.as_str()) =>
+        {
+            Some(HoverInfo {
+                name,
+                kind: HoverKind::Builtin,
+                value: None,
+            })
+        }
+        None => None,
+    }
+}
+
+fn parse_expr(program: &str) -> Option<Expression> {
+    let tokens = tokenize(program).ok()?;
+    let reduced = reduce_brackets_and_parenths(&tokens).ok()?;
+    get_expr(&reduced).ok()
+}
+
+/// Evaluates expressions that only consist of literals and operators.
+fn fold_constant(expr: &Expression) -> Option<VariableValue> {
+    match expr {
+        Expression::Value(val) => Some(val.clone()),
+        Expression::List(list) => list
+            .iter()
+            .map(fold_constant)
+            .collect::<Option<Vec<VariableValue>>>()
+            .map(VariableValue::List),
+        Expression::Object(fields) => fields
+            .iter()
+            .map(|(key, e)| fold_constant(e).map(|v| (key.clone(), v)))
+            .collect::<Option<IndexMap<String, VariableValue>>>()
+            .map(VariableValue::Object),
+        Expression::UnaryOperator(a, op, _) => evaluate_unary_op(fold_constant(a)?, *op).ok(),
+        Expression::BinaryOperator(a, b, op, _) => {
+            evaluate_binary_op(fold_constant(a)?, fold_constant(b)?, *op).ok()
+        }
+        _ => None,
+    }
+}
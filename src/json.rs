@@ -0,0 +1,315 @@
+use crate::*;
+
+impl VariableValue {
+    /// Serializes the value to JSON. `Unit`, `None` and functions become `null`, as do floats
+    /// that JSON can't represent (NaN and infinities). Bytes become an array of numbers and
+    /// object fields are written in the order they were defined in.
+    pub fn to_json(&self) -> String {
+        match self {
+            VariableValue::Int(n) => n.to_string(),
+            VariableValue::Float(n) if n.is_finite() => self.to_string(),
+            VariableValue::Float(_) => "null".to_string(),
+            VariableValue::Boolean(b) => b.to_string(),
+            VariableValue::String(s) => json_string(s),
+            VariableValue::Unit | VariableValue::None | VariableValue::Function(_, _) => {
+                "null".to_string()
+            }
+            VariableValue::List(list) => format!(
+                "[{}]",
+                list.iter()
+                    .map(|v| v.to_json())
+                    .collect::<Vec<String>>()
+                    .join(",")
+            ),
+            VariableValue::Bytes(bytes) => format!(
+                "[{}]",
+                bytes
+                    .iter()
+                    .map(|b| b.to_string())
+                    .collect::<Vec<String>>()
+                    .join(",")
+            ),
+            VariableValue::Frozen(value) => value.to_json(),
+            VariableValue::Object(object) => {
+                format!(
+                    "{{{}}}",
+                    object
+                        .iter()
+                        .map(|(key, v)| format!("{}:{}", json_string(key), v.to_json()))
+                        .collect::<Vec<String>>()
+                        .join(",")
+                )
+            }
+        }
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len() + 2);
+    escaped.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+/// Parses a JSON document. Numbers without a fraction or exponent become `Int`, other numbers
+/// `Float`, and `null` becomes `None`.
+pub fn from_json(json: &str) -> Result<VariableValue, RuntimeError> {
+    let mut parser = JsonParser {
+        chars: json.chars().collect(),
+        pos: 0,
+    };
+    let value = parser.parse_value()?;
+    parser.skip_whitespace();
+    if parser.pos < parser.chars.len() {
+        return Err(parser.error("unexpected trailing characters"));
+    }
+    Ok(value)
+}
+
+struct JsonParser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl JsonParser {
+    fn error(&self, message: &str) -> RuntimeError {
+        let before = &self.chars[..self.pos.min(self.chars.len())];
+        let line = before.iter().filter(|c| **c == '\n').count() + 1;
+        let column = before.iter().rev().take_while(|c| **c != '\n').count() + 1;
+        RuntimeError::from(format!(
+            "invalid JSON: {} at line {}, column {}",
+            message, line, column
+        ))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|c| c.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), RuntimeError> {
+        self.skip_whitespace();
+        if self.peek() == Some(expected) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", expected)))
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<VariableValue, RuntimeError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('{') => self.parse_object(),
+            Some('[') => self.parse_array(),
+            Some('"') => self.parse_string().map(VariableValue::String),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.parse_number(),
+            Some(c) if c.is_ascii_alphabetic() => {
+                let start = self.pos;
+                while self.peek().is_some_and(|c| c.is_ascii_alphabetic()) {
+                    self.pos += 1;
+                }
+                match self.chars[start..self.pos]
+                    .iter()
+                    .collect::<String>()
+                    .as_str()
+                {
+                    "true" => Ok(VariableValue::Boolean(true)),
+                    "false" => Ok(VariableValue::Boolean(false)),
+                    "null" => Ok(VariableValue::None),
+                    word => {
+                        self.pos = start;
+                        Err(self.error(&format!("unexpected '{}'", word)))
+                    }
+                }
+            }
+            Some(c) => Err(self.error(&format!("unexpected '{}'", c))),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn parse_object(&mut self) -> Result<VariableValue, RuntimeError> {
+        self.pos += 1;
+        let mut object = IndexMap::new();
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.pos += 1;
+            return Ok(VariableValue::Object(object));
+        }
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some('"') {
+                return Err(self.error("expected a string key"));
+            }
+            let key = self.parse_string()?;
+            self.expect(':')?;
+            let value = self.parse_value()?;
+            object.insert(key, value);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some('}') => {
+                    self.pos += 1;
+                    return Ok(VariableValue::Object(object));
+                }
+                _ => return Err(self.error("expected ',' or '}'")),
+            }
+        }
+    }
+
+    fn parse_array(&mut self) -> Result<VariableValue, RuntimeError> {
+        self.pos += 1;
+        let mut list = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.pos += 1;
+            return Ok(VariableValue::List(list));
+        }
+        loop {
+            list.push(self.parse_value()?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some(']') => {
+                    self.pos += 1;
+                    return Ok(VariableValue::List(list));
+                }
+                _ => return Err(self.error("expected ',' or ']'")),
+            }
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String, RuntimeError> {
+        self.pos += 1;
+        let mut s = String::new();
+        loop {
+            match self.peek() {
+                Some('"') => {
+                    self.pos += 1;
+                    return Ok(s);
+                }
+                Some('\\') => {
+                    self.pos += 1;
+                    let escaped = match self.peek() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => {
+                            let code = self.parse_unicode_escape()?;
+                            let code = if (0xd800..0xdc00).contains(&code)
+                                && self.chars.get(self.pos + 1..self.pos + 3) == Some(&['\\', 'u'])
+                            {
+                                self.pos += 2;
+                                let low = self.parse_unicode_escape()?;
+                                if !(0xdc00..0xe000).contains(&low) {
+                                    return Err(self.error("invalid unicode escape"));
+                                }
+                                0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00)
+                            } else {
+                                code
+                            };
+                            char::from_u32(code)
+                                .ok_or_else(|| self.error("invalid unicode escape"))?
+                        }
+                        _ => return Err(self.error("invalid escape sequence")),
+                    };
+                    s.push(escaped);
+                    self.pos += 1;
+                }
+                Some(c) if (c as u32) < 0x20 => {
+                    return Err(self.error("unescaped control character in string"))
+                }
+                Some(c) => {
+                    s.push(c);
+                    self.pos += 1;
+                }
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    /// Reads the four hex digits after `\u`, leaving `pos` on the last one.
+    fn parse_unicode_escape(&mut self) -> Result<u32, RuntimeError> {
+        let digits: String = self
+            .chars
+            .get(self.pos + 1..self.pos + 5)
+            .ok_or_else(|| self.error("invalid unicode escape"))?
+            .iter()
+            .collect();
+        let code = u32::from_str_radix(&digits, 16)
+            .ok()
+            .filter(|_| digits.chars().all(|c| c.is_ascii_hexdigit()))
+            .ok_or_else(|| self.error("invalid unicode escape"))?;
+        self.pos += 4;
+        Ok(code)
+    }
+
+    fn parse_number(&mut self) -> Result<VariableValue, RuntimeError> {
+        let start = self.pos;
+        if self.peek() == Some('-') {
+            self.pos += 1;
+        }
+        let digits = |parser: &mut Self| {
+            let digits_start = parser.pos;
+            while parser.peek().is_some_and(|c| c.is_ascii_digit()) {
+                parser.pos += 1;
+            }
+            parser.pos > digits_start
+        };
+        let leading_zero = self.peek() == Some('0');
+        if !digits(self) {
+            return Err(self.error("expected a digit"));
+        }
+        if leading_zero && self.pos - start > 1 + usize::from(self.chars[start] == '-') {
+            self.pos = start;
+            return Err(self.error("numbers cannot have leading zeros"));
+        }
+        let mut is_float = false;
+        if self.peek() == Some('.') {
+            self.pos += 1;
+            is_float = true;
+            if !digits(self) {
+                return Err(self.error("expected a digit"));
+            }
+        }
+        if matches!(self.peek(), Some('e') | Some('E')) {
+            self.pos += 1;
+            is_float = true;
+            if matches!(self.peek(), Some('+') | Some('-')) {
+                self.pos += 1;
+            }
+            if !digits(self) {
+                return Err(self.error("expected a digit"));
+            }
+        }
+        let literal: String = self.chars[start..self.pos].iter().collect();
+        match literal.parse::<i64>() {
+            Ok(n) if !is_float => Ok(VariableValue::Int(n)),
+            _ => literal
+                .parse::<f64>()
+                .map(VariableValue::Float)
+                .map_err(|_| self.error("invalid number")),
+        }
+    }
+}
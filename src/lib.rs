@@ -0,0 +1,34 @@
+use indexmap::IndexMap;
+
+use log::info;
+
+pub use builtin_functions::{BUILTIN_NAMES, MUTATING_BUILTIN_NAMES};
+pub use completion::*;
+pub use context::*;
+pub use errors::*;
+pub use executor::*;
+pub use formatter::*;
+pub use highlight::*;
+pub use hover::*;
+pub use json::from_json;
+pub use parser::*;
+pub use scope::*;
+pub use tokenizer::*;
+pub use variables::*;
+
+mod builtin_functions;
+mod completion;
+mod context;
+mod encoding;
+mod errors;
+mod executor;
+mod formatter;
+mod highlight;
+mod hover;
+mod json;
+mod parser;
+mod random;
+mod scope;
+mod time;
+mod tokenizer;
+mod variables;
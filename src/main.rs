@@ -1,24 +1,11 @@
 use std::{
-    collections::HashMap,
     env::{self, args},
     fs,
+    io::{self, BufRead, Write},
 };
 
-use errors::*;
-use executor::*;
-use log::{error, info};
-use parser::*;
-use scope::*;
-use tokenizer::*;
-use variables::*;
-
-mod builtin_functions;
-mod errors;
-mod executor;
-mod parser;
-mod scope;
-mod tokenizer;
-mod variables;
+use log::error;
+use slang::*;
 
 fn main() {
     env_logger::builder()
@@ -26,24 +13,126 @@ fn main() {
         .format_timestamp(None)
         .format_target(false)
         .init();
-    match run() {
-        Err(Error::S(e)) => error!("Syntax Error: {}", e.0),
-        Err(Error::R(e)) => error!("Runtime Error: {}", e.0),
-        Err(Error::C(e)) => error!("Client Error: {}", e.0),
-        Ok(()) => (),
+    if let Err(e) = run() {
+        report_error(e);
     }
 }
 
-fn run() -> Result<(), Error> {
-    let (program, cwd) = read_program_file()?;
-    execute_program(program, cwd).map(|_| ())
+fn report_error(e: Error) {
+    match e {
+        Error::S(e) => error!("Syntax Error: {}", e),
+        Error::R(e) => error!("Runtime Error: {}", e),
+        Error::C(e) => error!("Client Error: {}", e.0),
+    }
 }
 
-fn read_program_file() -> Result<(String, String), ClientError> {
-    let args: Vec<String> = args().collect();
+fn run() -> Result<(), Error> {
+    match args().nth(1).as_deref() {
+        Some("--fmt") => return format_program_file(),
+        None | Some("--repl") => return repl(),
+        _ => (),
+    }
+    let args: Vec<String> = args().skip(1).collect();
+    let print_result = args.iter().any(|arg| arg == "--print-result");
     let path = args
-        .get(1)
+        .iter()
+        .find(|arg| *arg != "--print-result")
         .ok_or(ClientError("No argument 'path' was given.".to_owned()))?;
+    let (program, cwd) = read_program_file(path)?;
+    // all syntax errors are reported before giving up, not just the first one
+    let statements = match parse_program(&program) {
+        Ok(statements) => statements,
+        Err(errors) => {
+            for e in errors {
+                report_error(Error::S(e).with_source(&program));
+            }
+            return Ok(());
+        }
+    };
+    let value = execute_statements(&mut Context::new(&cwd), &statements)
+        .map_err(|e| e.with_source(&program))?;
+    if print_result && !matches!(value, VariableValue::Unit) {
+        println!("{}", value);
+    }
+    Ok(())
+}
+
+/// Reads programs from stdin and runs them against one context, so definitions persist.
+/// Input with unclosed braces, brackets or parentheses is continued on the next line.
+fn repl() -> Result<(), Error> {
+    let cwd = env::current_dir().map_err(|e| ClientError(e.to_string()))?;
+    let mut ctx = Context::new(&cwd.to_string_lossy());
+    let mut input = String::new();
+    let mut lines = io::stdin().lock().lines();
+    loop {
+        print!("{}", if input.is_empty() { "> " } else { "... " });
+        io::stdout()
+            .flush()
+            .map_err(|e| ClientError(e.to_string()))?;
+        let Some(line) = lines.next() else {
+            println!();
+            return Ok(());
+        };
+        input.push_str(&line.map_err(|e| ClientError(e.to_string()))?);
+        input.push('\n');
+        if nesting_depth(&input) > 0 {
+            continue;
+        }
+        if !input.trim().is_empty() {
+            match execute_program(&mut ctx, &input) {
+                Ok(VariableValue::Unit) => (),
+                Ok(value) => println!("{}", value),
+                Err(e) => report_error(e),
+            }
+        }
+        input.clear();
+    }
+}
+
+fn nesting_depth(input: &str) -> i64 {
+    classify_tokens(input)
+        .into_iter()
+        .filter(|token| token.class == TokenClass::Punctuation)
+        .map(|token| match &input[token.span] {
+            "{" | "[" | "(" => 1,
+            "}" | "]" | ")" => -1,
+            _ => 0,
+        })
+        .sum()
+}
+
+fn format_program_file() -> Result<(), Error> {
+    let args: Vec<String> = args().skip(2).collect();
+    let mut options = FormatOptions::default();
+    let mut path = None;
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--tabs" => options.use_tabs = true,
+            "--indent" | "--max-width" => {
+                let val = args
+                    .get(i + 1)
+                    .and_then(|v| v.parse::<usize>().ok())
+                    .ok_or(ClientError(format!("'{}' expects a number.", args[i])))?;
+                if args[i] == "--indent" {
+                    options.indent_width = val;
+                } else {
+                    options.max_line_length = val;
+                }
+                i += 1;
+            }
+            p => path = Some(p.to_string()),
+        }
+        i += 1;
+    }
+    let path = path.ok_or(ClientError("No argument 'path' was given.".to_owned()))?;
+    let program = fs::read_to_string(&path)
+        .map_err(|e| ClientError(format!("Couldn't read file at {}: {}", path, e)))?;
+    print!("{}", format_program(&program, &options)?);
+    Ok(())
+}
+
+fn read_program_file(path: &str) -> Result<(String, String), ClientError> {
     let program = fs::read_to_string(path)
         .map_err(|e| ClientError(format!("Couldn't read file at {}: {}", path, e)))?;
     let cwd = env::current_dir()
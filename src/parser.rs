@@ -1,98 +1,159 @@
-use std::collections::HashMap;
-
+use indexmap::IndexMap;
 use log::{debug, info};
 
 use crate::{
     errors::SyntaxError,
-    tokenizer::{Keyword, Token},
-    variables::{Operator, VariableValue},
+    tokenizer::{tokenize, Comment, Keyword, Span, Spanned, Token},
+    variables::{evaluate_unary_op, Operator, VariableValue},
 };
 
 #[derive(Debug, Clone)]
 pub enum Statement {
     VariableDefinition(String, Expression),
-    VariableAssignment(ReferenceExpr, Expression),
+    ConstDefinition(String, Expression),
+    VariableAssignment(Box<ReferenceExpr>, Expression),
     Expr(Expression),
     Return(Expression),
     Break(Expression),
     Continue,
     ImplicitReturn(Expression),
+    Comment(Comment),
 }
 
+pub type NamedArgs = Vec<(String, Expression)>;
+
 #[derive(Debug, Clone)]
 pub enum Expression {
     Value(VariableValue),
     List(Vec<Expression>),
-    Object(HashMap<String, Expression>),
+    Object(IndexMap<String, Expression>),
     Reference(Box<ReferenceExpr>),
-    BinaryOperator(Box<Expression>, Box<Expression>, Operator),
-    UnaryOperator(Box<Expression>, Operator),
+    BinaryOperator(Box<Expression>, Box<Expression>, Operator, Span),
+    UnaryOperator(Box<Expression>, Operator, Span),
     Block(Vec<Statement>),
-    FunctionCall(Box<Expression>, Vec<Expression>),
+    /// A call with its positional and its named arguments, e.g. `print(a, b, sep = ", ")`.
+    FunctionCall(
+        Box<Expression>,
+        Vec<Expression>,
+        Vec<(String, Expression)>,
+        Span,
+    ),
     BuiltinFunctionCall(String, Option<VariableValue>, Vec<VariableValue>),
     IfElse(Box<Expression>, Box<Expression>, Option<Box<Expression>>),
-    ForLoop(String, Box<Expression>, Box<Expression>),
+    ForLoop(Binding, Box<Expression>, Box<Expression>),
     WhileLoop(Box<Expression>, Box<Expression>),
+    Match(Box<Expression>, Vec<(Pattern, Expression)>, Span),
+    /// `let name = value in body`, where `name` is only defined in `body`.
+    Let(String, Box<Expression>, Box<Expression>),
 }
 
+/// A parameter of a function, with the expression its value defaults to if the argument is left
+/// out. A rest parameter, written `...name`, collects all remaining arguments into a list.
 #[derive(Debug, Clone)]
-pub enum ReferenceExpr {
+pub struct Parameter {
+    pub name: String,
+    pub default: Option<Expression>,
+    pub rest: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum Binding {
     Variable(String),
-    Index(Expression, Expression),
-    Object(Expression, String),
+    List(Vec<String>),
+}
+
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    Value(VariableValue),
+    Wildcard,
+}
+
+#[derive(Debug, Clone)]
+pub enum ReferenceExpr {
+    Variable(String, Span),
+    Index(Expression, Expression, Span),
+    Object(Expression, String, Span),
+}
+
+impl ReferenceExpr {
+    pub fn span(&self) -> Span {
+        match self {
+            ReferenceExpr::Variable(_, span)
+            | ReferenceExpr::Index(_, _, span)
+            | ReferenceExpr::Object(_, _, span) => *span,
+        }
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum PartialParsed {
-    Token(Token),
-    Braces(Vec<PartialParsed>),
-    Parentheses(Vec<PartialParsed>),
-    Brackets(Vec<PartialParsed>),
-    Closure(Vec<String>),
+    Token(Token, Span),
+    Braces(Vec<PartialParsed>, Span),
+    Parentheses(Vec<PartialParsed>, Span),
+    Brackets(Vec<PartialParsed>, Span),
+    Closure(Vec<Parameter>, Span),
+}
+
+impl PartialParsed {
+    pub fn span(&self) -> Span {
+        match self {
+            PartialParsed::Token(_, span)
+            | PartialParsed::Braces(_, span)
+            | PartialParsed::Parentheses(_, span)
+            | PartialParsed::Brackets(_, span)
+            | PartialParsed::Closure(_, span) => *span,
+        }
+    }
 }
 
-pub fn reduce_brackets_and_parenths(t: &[Token]) -> Result<Vec<PartialParsed>, SyntaxError> {
+pub fn reduce_brackets_and_parenths(
+    t: &[Spanned<Token>],
+) -> Result<Vec<PartialParsed>, SyntaxError> {
     let mut reduced_t = Vec::new();
 
     let mut i = 0;
     while i < t.len() {
-        match t.get(i) {
-            Some(Token::OpeningParethesis) => {
+        let span = t[i].span;
+        match &t[i].node {
+            Token::OpeningParethesis => {
                 let closing = find_matching(&t[i + 1..], |tkn| {
                     matches!(tkn, Some(Token::ClosingParethesis))
                 })
-                .ok_or(SyntaxError::from("No matching closing parenthesis!"))?;
+                .ok_or(SyntaxError::from("No matching closing parenthesis!").at(span))?;
                 let reduced = reduce_brackets_and_parenths(&t[i + 1..i + 1 + closing])?;
-                reduced_t.push(PartialParsed::Parentheses(reduced));
+                reduced_t.push(PartialParsed::Parentheses(strip_comments(&reduced), span));
                 i += closing + 1;
             }
-            Some(Token::OpeningBrace) => {
+            Token::OpeningBrace => {
                 let closing =
                     find_matching(&t[i + 1..], |tkn| matches!(tkn, Some(Token::ClosingBrace)))
-                        .ok_or(SyntaxError::from("No matching closing brace!"))?;
+                        .ok_or(SyntaxError::from("No matching closing brace!").at(span))?;
                 let reduced = reduce_brackets_and_parenths(&t[i + 1..i + 1 + closing])?;
-                reduced_t.push(PartialParsed::Braces(reduced));
+                reduced_t.push(PartialParsed::Braces(reduced, span));
                 i += closing + 1;
             }
-            Some(Token::OpeningBracket) => {
+            Token::OpeningBracket => {
                 let closing = find_matching(&t[i + 1..], |tkn| {
                     matches!(tkn, Some(Token::ClosingBracket))
                 })
-                .ok_or(SyntaxError::from("No matching closing bracket!"))?;
+                .ok_or(SyntaxError::from("No matching closing bracket!").at(span))?;
                 let reduced = reduce_brackets_and_parenths(&t[i + 1..i + 1 + closing])?;
-                reduced_t.push(PartialParsed::Brackets(reduced));
+                reduced_t.push(PartialParsed::Brackets(strip_comments(&reduced), span));
                 i += closing + 1;
             }
-            Some(Token::VerticalBar) => {
+            Token::VerticalBar => {
                 let closing =
                     find_matching(&t[i + 1..], |tkn| matches!(tkn, Some(Token::VerticalBar)))
-                        .ok_or(SyntaxError::from("No matching closing bracket!"))?;
-                let reduced = get_comma_separated_identifiers(&t[i + 1..i + 1 + closing])?;
-                reduced_t.push(PartialParsed::Closure(reduced));
+                        .ok_or(SyntaxError::from("No matching closing bracket!").at(span))?;
+                let params = reduce_brackets_and_parenths(&t[i + 1..i + 1 + closing])?;
+                let params = get_parameters(&strip_comments(&params)).map_err(|e| e.at(span))?;
+                reduced_t.push(PartialParsed::Closure(params, span));
                 i += closing + 1;
             }
-            Some(tkn) => reduced_t.push(PartialParsed::Token(tkn.clone())),
-            None => (),
+            Token::Operator(Operator::Or) if !follows_operand(reduced_t.last()) => {
+                reduced_t.push(PartialParsed::Closure(Vec::new(), span));
+            }
+            tkn => reduced_t.push(PartialParsed::Token(tkn.clone(), span)),
         }
         i += 1;
     }
@@ -100,13 +161,53 @@ pub fn reduce_brackets_and_parenths(t: &[Token]) -> Result<Vec<PartialParsed>, S
     Ok(reduced_t)
 }
 
+/// Whether a token after `prev` is in binary operator position, which tells `||` as the or
+/// operator apart from a closure without parameters.
+fn follows_operand(prev: Option<&PartialParsed>) -> bool {
+    match prev {
+        Some(PartialParsed::Token(Token::Value(_) | Token::Identifier(_), _)) => true,
+        Some(PartialParsed::Token(_, _)) | None => false,
+        Some(_) => true,
+    }
+}
+
+/// Comments are only kept between statements; everywhere else they are dropped.
+fn strip_comments(t: &[PartialParsed]) -> Vec<PartialParsed> {
+    t.iter()
+        .filter(|tkn| !matches!(tkn, PartialParsed::Token(Token::Comment(_), _)))
+        .cloned()
+        .collect()
+}
+
+/// Parses a whole program. A statement that fails to parse is skipped up to the next top-level
+/// semicolon, so the errors of all statements are returned together.
+pub fn parse_program(program: &str) -> Result<Vec<Statement>, Vec<SyntaxError>> {
+    let tokens = tokenize(program).map_err(|e| vec![e])?;
+    let reduced = reduce_brackets_and_parenths(&tokens).map_err(|e| vec![e])?;
+    let mut errors = Vec::new();
+    match collect_statements(&reduced, Some(&mut errors)) {
+        Ok(_) if !errors.is_empty() => Err(errors),
+        Ok(statements) => Ok(statements),
+        Err(e) => Err(vec![e]),
+    }
+}
+
 pub fn get_statements(t: &[PartialParsed]) -> Result<Vec<Statement>, SyntaxError> {
+    collect_statements(t, None)
+}
+
+/// If `errors` is given, statements that fail to parse are left out and their errors are pushed
+/// to it instead of being returned.
+fn collect_statements(
+    t: &[PartialParsed],
+    mut errors: Option<&mut Vec<SyntaxError>>,
+) -> Result<Vec<Statement>, SyntaxError> {
     let mut statements = Vec::new();
     let semis: Vec<usize> = t
         .iter()
         .enumerate()
         .filter_map(|(i, tkn)| {
-            if matches!(tkn, PartialParsed::Token(Token::Semicolon)) {
+            if matches!(tkn, PartialParsed::Token(Token::Semicolon, _)) {
                 Some(i)
             } else {
                 None
@@ -116,43 +217,99 @@ pub fn get_statements(t: &[PartialParsed]) -> Result<Vec<Statement>, SyntaxError
     for i in 0..=semis.len() {
         let start = if i == 0 { 0 } else { semis[i - 1] + 1 };
         let end = if i == semis.len() { t.len() } else { semis[i] };
-        if end - start > 0 {
-            let stmnt = get_stmnt(&t[start..end])?;
-
-            info!("statement: {:?}", stmnt);
-            statements.push(stmnt);
+        let segment = &t[start..end];
+        // comments before the statement lead it, comments inside or after it trail it
+        let code_start = segment
+            .iter()
+            .position(|tkn| !matches!(tkn, PartialParsed::Token(Token::Comment(_), _)))
+            .unwrap_or(segment.len());
+        let comments = |tkns: &[PartialParsed]| -> Vec<Statement> {
+            tkns.iter()
+                .filter_map(|tkn| match tkn {
+                    PartialParsed::Token(Token::Comment(comment), _) => {
+                        Some(Statement::Comment(comment.clone()))
+                    }
+                    _ => None,
+                })
+                .collect()
+        };
+        statements.extend(comments(&segment[..code_start]));
+        let code = strip_comments(&segment[code_start..]);
+        if let Some(first) = code.first() {
+            match (
+                get_stmnt(&code).map_err(|e| e.at(first.span())),
+                errors.as_mut(),
+            ) {
+                (Ok(stmnt), _) => {
+                    info!("statement: {:?}", stmnt);
+                    statements.push(stmnt);
+                }
+                (Err(e), Some(errors)) => errors.push(e),
+                (Err(e), None) => return Err(e),
+            }
         }
+        statements.extend(comments(&segment[code_start..]));
     }
 
-    if let Some(PartialParsed::Token(Token::Semicolon)) = t.last() {
+    if let Some(PartialParsed::Token(Token::Semicolon, _)) = t
+        .iter()
+        .rfind(|tkn| !matches!(tkn, PartialParsed::Token(Token::Comment(_), _)))
+    {
         return Ok(statements);
     }
 
-    if let Some(last_stmnt) = statements.last_mut() {
+    if let Some(last_stmnt) = statements
+        .iter_mut()
+        .rfind(|stmnt| !matches!(stmnt, Statement::Comment(_)))
+    {
         if let Statement::Expr(ref last_expr) = last_stmnt {
             *last_stmnt = Statement::ImplicitReturn(last_expr.clone());
         }
         Ok(statements)
+    } else if errors.is_some_and(|errors| !errors.is_empty()) {
+        Ok(statements)
     } else {
         Err("empty block!".into())
     }
 }
 
 pub fn get_stmnt(t: &[PartialParsed]) -> Result<Statement, SyntaxError> {
-    if let Some(PartialParsed::Token(Token::Keyword(Keyword::Let))) = t.get(0) {
+    if let Some(PartialParsed::Token(Token::Keyword(Keyword::Let), _)) = t.first() {
+        if find_let_in(t).is_some() {
+            return Ok(Statement::Expr(get_expr(t)?));
+        }
         if let (
-            Some(PartialParsed::Token(Token::Identifier(var_name))),
-            Some(PartialParsed::Token(Token::Assign)),
+            Some(PartialParsed::Token(Token::Identifier(var_name), _)),
+            Some(PartialParsed::Token(Token::Assign, _)),
         ) = (t.get(1), t.get(2))
         {
             let expr = get_expr(&t[3..])?;
             return Ok(Statement::VariableDefinition(var_name.to_string(), expr));
+        } else if let [_, PartialParsed::Token(Token::Identifier(var_name), _)] = t {
+            // `let x;` defines `x` without a value
+            return Ok(Statement::VariableDefinition(
+                var_name.to_string(),
+                Expression::Value(VariableValue::None),
+            ));
         } else {
             return Err("Invalid VariableDefinition Statement".into());
         }
     }
 
-    if let Some(PartialParsed::Token(Token::Keyword(Keyword::Return))) = t.get(0) {
+    if let Some(PartialParsed::Token(Token::Keyword(Keyword::Const), _)) = t.first() {
+        if let (
+            Some(PartialParsed::Token(Token::Identifier(var_name), _)),
+            Some(PartialParsed::Token(Token::Assign, _)),
+        ) = (t.get(1), t.get(2))
+        {
+            let expr = get_expr(&t[3..])?;
+            return Ok(Statement::ConstDefinition(var_name.to_string(), expr));
+        } else {
+            return Err("Invalid ConstDefinition Statement".into());
+        }
+    }
+
+    if let Some(PartialParsed::Token(Token::Keyword(Keyword::Return), _)) = t.first() {
         return if t.len() > 1 {
             let expr = get_expr(&t[1..])?;
             Ok(Statement::Return(expr))
@@ -161,7 +318,7 @@ pub fn get_stmnt(t: &[PartialParsed]) -> Result<Statement, SyntaxError> {
         };
     }
 
-    if let Some(PartialParsed::Token(Token::Keyword(Keyword::Break))) = t.get(0) {
+    if let Some(PartialParsed::Token(Token::Keyword(Keyword::Break), _)) = t.first() {
         return if t.len() > 1 {
             let expr = get_expr(&t[1..])?;
             Ok(Statement::Break(expr))
@@ -170,9 +327,9 @@ pub fn get_stmnt(t: &[PartialParsed]) -> Result<Statement, SyntaxError> {
         };
     }
 
-    if let Some(PartialParsed::Token(Token::Keyword(Keyword::Continue))) = t.get(0) {
+    if let Some(PartialParsed::Token(Token::Keyword(Keyword::Continue), _)) = t.first() {
         return if t.len() > 1 {
-            Err(SyntaxError("invalid statement after continue".into()))
+            Err("invalid statement after continue".into())
         } else {
             Ok(Statement::Continue)
         };
@@ -180,19 +337,18 @@ pub fn get_stmnt(t: &[PartialParsed]) -> Result<Statement, SyntaxError> {
 
     if let Some(i) = t
         .iter()
-        .position(|tkn| matches!(tkn, PartialParsed::Token(Token::Assign)))
+        .position(|tkn| matches!(tkn, PartialParsed::Token(Token::Assign, _)))
     {
         let expr = get_expr(&t[..i])?;
         let val_expr = get_expr(&t[i + 1..])?;
-        if let Expression::Reference(ref_expr) = expr {
-            return Ok(Statement::VariableAssignment(*ref_expr, val_expr));
-        } else {
-            return Err("can only assign to a reference".into());
-        }
+        return Ok(Statement::VariableAssignment(
+            Box::new(assignment_target(&expr, t[0].span())?),
+            val_expr,
+        ));
     }
 
     if let Some((i, op)) = t.iter().enumerate().find_map(|(i, tkn)| {
-        if let PartialParsed::Token(Token::OperatorAssign(op)) = tkn {
+        if let PartialParsed::Token(Token::OperatorAssign(op), _) = tkn {
             Some((i, op))
         } else {
             None
@@ -200,55 +356,85 @@ pub fn get_stmnt(t: &[PartialParsed]) -> Result<Statement, SyntaxError> {
     }) {
         let expr = get_expr(&t[..i])?;
         let val_expr = get_expr(&t[i + 1..])?;
-        if let Expression::Reference(ref ref_expr) = expr {
-            return Ok(Statement::VariableAssignment(
-                *ref_expr.clone(),
-                Expression::BinaryOperator(Box::new(expr), Box::new(val_expr), op.clone()),
-            ));
-        } else {
-            return Err("can only assign to a reference".into());
-        }
+        return Ok(Statement::VariableAssignment(
+            Box::new(assignment_target(&expr, t[0].span())?),
+            Expression::BinaryOperator(Box::new(expr), Box::new(val_expr), *op, t[i].span()),
+        ));
     }
 
     get_expr(t).map(|e| Statement::Expr(e))
 }
 
+/// Errors are reported at the innermost expression they occur in.
+/// The position of the `in` that belongs to the `let` at the start of `t`, skipping those of
+/// nested `for` loops and `let` expressions.
+fn find_let_in(t: &[PartialParsed]) -> Option<usize> {
+    let mut nested = 0;
+    for (i, tkn) in t.iter().enumerate().skip(1) {
+        match tkn {
+            PartialParsed::Token(Token::Keyword(Keyword::For | Keyword::Let), _) => nested += 1,
+            PartialParsed::Token(Token::Keyword(Keyword::In), _) if nested == 0 => return Some(i),
+            PartialParsed::Token(Token::Keyword(Keyword::In), _) => nested -= 1,
+            _ => (),
+        }
+    }
+    None
+}
+
+/// The reference an assignment writes to. `span` is where the target starts.
+fn assignment_target(expr: &Expression, span: Span) -> Result<ReferenceExpr, SyntaxError> {
+    match expr {
+        Expression::Reference(ref_expr) => Ok(*ref_expr.clone()),
+        Expression::FunctionCall(..) => {
+            Err(SyntaxError::from("cannot assign to the result of a function call").at(span))
+        }
+        _ => Err(SyntaxError::from("can only assign to a reference").at(span)),
+    }
+}
+
 pub fn get_expr(t: &[PartialParsed]) -> Result<Expression, SyntaxError> {
+    get_expr_unlocated(t).map_err(|e| match t.first() {
+        Some(first) => e.at(first.span()),
+        None => e,
+    })
+}
+
+fn get_expr_unlocated(t: &[PartialParsed]) -> Result<Expression, SyntaxError> {
     debug!("get expr: {:?}", t);
     if t.len() == 0 {
         return Err("Empty expr".into());
     }
     if t.len() == 1 {
         return match t[0] {
-            PartialParsed::Braces(ref b) => {
+            PartialParsed::Braces(ref b, _) => {
                 if b.len() == 0 {
-                    Ok(Expression::Value(VariableValue::Object(HashMap::new())))
+                    Ok(Expression::Value(VariableValue::Object(IndexMap::new())))
                 } else if b
                     .iter()
-                    .any(|v| matches!(v, PartialParsed::Token(Token::Colon)))
+                    .any(|v| matches!(v, PartialParsed::Token(Token::Colon, _)))
                 {
-                    get_object(b)
+                    get_object(&strip_comments(b))
                 } else {
                     get_statements(b).map(|v| Expression::Block(v))
                 }
             }
-            PartialParsed::Parentheses(ref b) => {
+            PartialParsed::Parentheses(ref b, _) => {
                 if b.len() == 0 {
                     Ok(Expression::Value(VariableValue::Unit))
                 } else {
                     get_expr(b)
                 }
             }
-            PartialParsed::Brackets(ref b) => if b.len() > 0 {
+            PartialParsed::Brackets(ref b, _) => if b.len() > 0 {
                 get_comma_separated_exprs(b)
             } else {
                 Ok(Vec::new())
             }
             .map(|v| Expression::List(v)),
-            PartialParsed::Token(ref b) => match b {
+            PartialParsed::Token(ref b, _) => match b {
                 Token::Value(v) => Ok(Expression::Value(v.clone())),
                 Token::Identifier(v) => Ok(Expression::Reference(Box::new(
-                    ReferenceExpr::Variable(v.to_string()),
+                    ReferenceExpr::Variable(v.to_string(), t[0].span()),
                 ))),
                 _ => Err("Not a valid token expr".into()),
             },
@@ -256,9 +442,30 @@ pub fn get_expr(t: &[PartialParsed]) -> Result<Expression, SyntaxError> {
         };
     }
 
-    let is_closure = t.iter().any(|tkn| matches!(tkn, PartialParsed::Closure(_)));
+    if let Some(PartialParsed::Token(Token::Keyword(Keyword::Let), span)) = t.first() {
+        let (
+            Some(i),
+            Some(PartialParsed::Token(Token::Identifier(var_name), _)),
+            Some(PartialParsed::Token(Token::Assign, _)),
+        ) = (find_let_in(t), t.get(1), t.get(2))
+        else {
+            return Err(SyntaxError::from("invalid let expression").at(*span));
+        };
+        if i == 3 || i + 1 == t.len() {
+            return Err(SyntaxError::from("invalid let expression").at(*span));
+        }
+        return Ok(Expression::Let(
+            var_name.to_string(),
+            Box::new(get_expr(&t[3..i])?),
+            Box::new(get_expr(&t[i + 1..])?),
+        ));
+    }
+
+    let is_closure = t
+        .iter()
+        .any(|tkn| matches!(tkn, PartialParsed::Closure(_, _)));
     if is_closure {
-        if let Some(PartialParsed::Closure(args)) = t.get(0) {
+        if let Some(PartialParsed::Closure(args, _)) = t.first() {
             let expr = get_expr(&t[1..])?;
 
             return Ok(Expression::Value(VariableValue::Function(
@@ -266,52 +473,53 @@ pub fn get_expr(t: &[PartialParsed]) -> Result<Expression, SyntaxError> {
                 Box::new(expr),
             )));
         } else {
-            return Err(format!("invalid closure expresion: {:?}", t).into());
+            return Err("invalid closure expression".into());
         }
     }
 
     let is_for_loop = t
         .iter()
-        .any(|tkn| matches!(tkn, PartialParsed::Token(Token::Keyword(Keyword::For))));
+        .any(|tkn| matches!(tkn, PartialParsed::Token(Token::Keyword(Keyword::For), _)));
     if is_for_loop {
         if let (
-            Some(PartialParsed::Token(Token::Keyword(Keyword::For))),
-            Some(PartialParsed::Token(Token::Identifier(var_name))),
-            Some(PartialParsed::Token(Token::Keyword(Keyword::In))),
+            Some(PartialParsed::Token(Token::Keyword(Keyword::For), _)),
+            Some(binding_tkn),
+            Some(PartialParsed::Token(Token::Keyword(Keyword::In), _)),
         ) = (t.get(0), t.get(1), t.get(2))
         {
             if t.len() < 5 {
-                return Err(format!("invalid for loop: {:?}", t).into());
+                return Err("invalid for loop".into());
             }
+            let binding = get_binding(binding_tkn)?;
             let iterator = get_expr(&t[3..t.len() - 1])?;
             let body_expr = get_expr(&t[t.len() - 1..])?;
             return match body_expr {
                 Expression::Block(body) => Ok(Expression::ForLoop(
-                    var_name.to_string(),
+                    binding,
                     Box::new(iterator),
                     Box::new(Expression::Block(body)),
                 )),
                 Expression::Value(VariableValue::Object(map)) if map.len() == 0 => {
                     Ok(Expression::ForLoop(
-                        var_name.to_string(),
+                        binding,
                         Box::new(iterator),
                         Box::new(Expression::Block(Vec::new())),
                     ))
                 }
-                _ => Err(format!("invalid for loop body: {:?}", t).into()),
+                _ => Err("invalid for loop body".into()),
             };
         } else {
-            return Err(format!("invalid for loop: {:?}", t).into());
+            return Err("invalid for loop".into());
         }
     }
 
     let is_while_loop = t
         .iter()
-        .any(|tkn| matches!(tkn, PartialParsed::Token(Token::Keyword(Keyword::While))));
+        .any(|tkn| matches!(tkn, PartialParsed::Token(Token::Keyword(Keyword::While), _)));
     if is_while_loop {
-        if let Some(PartialParsed::Token(Token::Keyword(Keyword::While))) = t.get(0) {
+        if let Some(PartialParsed::Token(Token::Keyword(Keyword::While), _)) = t.first() {
             if t.len() < 3 {
-                return Err(format!("invalid while loop: {:?}", t).into());
+                return Err("invalid while loop".into());
             }
             let condition = get_expr(&t[1..t.len() - 1])?;
             let body_expr = get_expr(&t[t.len() - 1..])?;
@@ -326,23 +534,46 @@ pub fn get_expr(t: &[PartialParsed]) -> Result<Expression, SyntaxError> {
                         Box::new(Expression::Block(Vec::new())),
                     ))
                 }
-                _ => Err(format!("invalid while loop body: {:?}", t).into()),
+                _ => Err("invalid while loop body".into()),
             };
         } else {
-            return Err(format!("invalid while loop: {:?}", t).into());
+            return Err("invalid while loop".into());
+        }
+    }
+
+    let is_match = t
+        .iter()
+        .any(|tkn| matches!(tkn, PartialParsed::Token(Token::Keyword(Keyword::Match), _)));
+    if is_match {
+        if let (
+            Some(PartialParsed::Token(Token::Keyword(Keyword::Match), span)),
+            Some(PartialParsed::Braces(arms, _)),
+        ) = (t.first(), t.last())
+        {
+            if t.len() < 3 {
+                return Err("invalid match expression".into());
+            }
+            let scrutinee = get_expr(&t[1..t.len() - 1])?;
+            return Ok(Expression::Match(
+                Box::new(scrutinee),
+                get_match_arms(&strip_comments(arms))?,
+                *span,
+            ));
+        } else {
+            return Err("invalid match expression".into());
         }
     }
 
     let if_pos = t
         .iter()
-        .position(|tkn| matches!(tkn, PartialParsed::Token(Token::Keyword(Keyword::If))));
+        .position(|tkn| matches!(tkn, PartialParsed::Token(Token::Keyword(Keyword::If), _)));
     if let Some(if_i) = if_pos {
         if let Some(else_pos) = t
             .iter()
-            .position(|tkn| matches!(tkn, PartialParsed::Token(Token::Keyword(Keyword::Else))))
+            .position(|tkn| matches!(tkn, PartialParsed::Token(Token::Keyword(Keyword::Else), _)))
         {
             if t.len() < 5 {
-                return Err(format!("invalid if statement: {:?}", t).into());
+                return Err("invalid if statement".into());
             }
             let cond = get_expr(&t[if_i + 1..else_pos - 1])?;
 
@@ -356,11 +587,11 @@ pub fn get_expr(t: &[PartialParsed]) -> Result<Expression, SyntaxError> {
                     Some(Box::new(Expression::Block(e2))),
                 ));
             } else {
-                return Err(format!("invalid if else body: {:?}", t).into());
+                return Err("invalid if else body".into());
             }
         } else {
             if t.len() < 3 {
-                return Err(format!("invalid if statement: {:?}", t).into());
+                return Err("invalid if statement".into());
             }
             let cond = get_expr(&t[if_i + 1..t.len() - 1])?;
 
@@ -371,7 +602,7 @@ pub fn get_expr(t: &[PartialParsed]) -> Result<Expression, SyntaxError> {
                     None,
                 ));
             } else {
-                return Err(format!("invalid if body: {:?}", t).into());
+                return Err("invalid if body".into());
             }
         }
     }
@@ -380,8 +611,17 @@ pub fn get_expr(t: &[PartialParsed]) -> Result<Expression, SyntaxError> {
     let mut lowest_precedence = None;
     for i in 0..t.len() {
         match t[i] {
-            PartialParsed::Token(Token::Operator(ref op)) => {
-                let prec = op.precedence();
+            // the body of a `let` expression extends to the end
+            PartialParsed::Token(Token::Keyword(Keyword::Let), _) => break,
+            // an operator right after another one is a prefix of the operand that follows
+            PartialParsed::Token(Token::Operator(_), _)
+                if i > 0 && matches!(t[i - 1], PartialParsed::Token(Token::Operator(_), _)) => {}
+            PartialParsed::Token(Token::Operator(ref op), _) => {
+                // a leading operator is a prefix, which binds tighter than any binary operator
+                let prec = match i {
+                    0 => prefix_operator(*op).map_or(op.precedence(), |op| op.precedence()),
+                    _ => op.precedence(),
+                };
                 if !lowest_precedence.is_some_and(|v| v < prec) {
                     lowest_precedence = Some(prec);
                     lowest_op = Some((i, op.clone()));
@@ -394,67 +634,101 @@ pub fn get_expr(t: &[PartialParsed]) -> Result<Expression, SyntaxError> {
     if let Some((i, op)) = lowest_op {
         if i == 0 {
             let e = get_expr(&t[i + 1..])?;
-            let unary_op = match op {
-                Operator::Add => Ok(Operator::UnaryPlus),
-                Operator::Subtract => Ok(Operator::Negate),
-                Operator::Not => Ok(Operator::Not),
-                _ => Err(SyntaxError::from("no such unary operator")),
-            }?;
-            return Ok(Expression::UnaryOperator(Box::new(e), unary_op));
+            let unary_op = prefix_operator(op)?;
+            return Ok(Expression::UnaryOperator(
+                Box::new(e),
+                unary_op,
+                t[0].span(),
+            ));
         } else {
+            if i + 1 == t.len() {
+                return Err(SyntaxError::from(format!(
+                    "missing operand after '{}'",
+                    Token::Operator(op)
+                ))
+                .at(t[i].span()));
+            }
             let e1 = get_expr(&t[..i])?;
             let e2 = get_expr(&t[i + 1..])?;
 
-            return Ok(Expression::BinaryOperator(Box::new(e1), Box::new(e2), op));
+            return Ok(Expression::BinaryOperator(
+                Box::new(e1),
+                Box::new(e2),
+                op,
+                t[i].span(),
+            ));
         }
     }
 
-    if let Some(PartialParsed::Parentheses(p)) = t.last() {
+    if let Some(PartialParsed::Parentheses(p, _)) = t.last() {
         let fun = get_expr(&t[..t.len() - 1])?;
-        let args = if p.len() > 0 {
-            get_comma_separated_exprs(p)?
-        } else {
-            Vec::new()
-        };
-        return Ok(Expression::FunctionCall(Box::new(fun), args));
+        let (args, named_args) = get_call_args(p)?;
+        return Ok(Expression::FunctionCall(
+            Box::new(fun),
+            args,
+            named_args,
+            t[0].span(),
+        ));
     }
 
-    if let Some(PartialParsed::Brackets(p)) = t.last() {
+    if let Some(PartialParsed::Brackets(p, span)) = t.last() {
         let fun = get_expr(&t[..t.len() - 1])?;
         let index = get_expr(p)?;
         return Ok(Expression::Reference(Box::new(ReferenceExpr::Index(
-            fun, index,
+            fun, index, *span,
         ))));
     }
 
     if let (
-        Some(PartialParsed::Token(Token::Dot)),
-        Some(PartialParsed::Token(Token::Identifier(var_name))),
+        Some(PartialParsed::Token(Token::Dot, span)),
+        Some(PartialParsed::Token(Token::Identifier(var_name), _)),
     ) = (t.get(t.len() - 2), t.last())
     {
         let fun = get_expr(&t[..t.len() - 2])?;
         return Ok(Expression::Reference(Box::new(ReferenceExpr::Object(
             fun,
             var_name.to_string(),
+            *span,
         ))));
     }
 
-    Err(format!("Not a valid expr: {:?}. Are you missing a semicolon?", t).into())
+    Err("Not a valid expr. Are you missing a semicolon?".into())
+}
+
+/// The operator a token stands for when it is written before its operand.
+fn prefix_operator(op: Operator) -> Result<Operator, SyntaxError> {
+    match op {
+        Operator::Add => Ok(Operator::UnaryPlus),
+        Operator::Subtract => Ok(Operator::Negate),
+        Operator::Not => Ok(Operator::Not),
+        Operator::BitNot => Ok(Operator::BitNot),
+        _ => Err(SyntaxError::from("no such unary operator")),
+    }
+}
+
+/// Drops a comma after the last element of a list, object, argument or parameter list, so
+/// `[1, 2,]` is `[1, 2]`. A comma on its own is left alone and still an error.
+fn strip_trailing_comma(t: &[PartialParsed]) -> &[PartialParsed] {
+    match t {
+        [init @ .., PartialParsed::Token(Token::Comma, _)] if !init.is_empty() => init,
+        _ => t,
+    }
 }
 
 pub fn get_object(t: &[PartialParsed]) -> Result<Expression, SyntaxError> {
+    let t = strip_trailing_comma(t);
     let commas: Vec<usize> = t
         .iter()
         .enumerate()
         .filter_map(|(i, tkn)| {
-            if matches!(tkn, PartialParsed::Token(Token::Comma)) {
+            if matches!(tkn, PartialParsed::Token(Token::Comma, _)) {
                 Some(i)
             } else {
                 None
             }
         })
         .collect();
-    let mut exprs = HashMap::with_capacity(commas.len() + 1);
+    let mut exprs = IndexMap::with_capacity(commas.len() + 1);
     for i in 0..=commas.len() {
         let start = if i == 0 { 0 } else { commas[i - 1] + 1 };
         let end = if i == commas.len() {
@@ -464,32 +738,36 @@ pub fn get_object(t: &[PartialParsed]) -> Result<Expression, SyntaxError> {
         };
 
         if let (
-            PartialParsed::Token(Token::Identifier(ref var_name)),
-            PartialParsed::Token(Token::Colon),
-        ) = (&t[start], &t[start + 1])
+            Some(PartialParsed::Token(Token::Identifier(ref var_name), span)),
+            Some(PartialParsed::Token(Token::Colon, _)),
+        ) = (t[start..end].first(), t[start..end].get(1))
         {
-            let expr = get_expr(&t[start + 2..end])?;
+            let expr = get_expr(&t[start + 2..end]).map_err(|e| e.at(*span))?;
             exprs.insert(var_name.to_string(), expr);
         } else {
-            return Err("invalid variable name".into());
+            let error = SyntaxError::from("invalid variable name");
+            return Err(match t.get(start) {
+                Some(tkn) => error.at(tkn.span()),
+                None => error,
+            });
         }
     }
     Ok(Expression::Object(exprs))
 }
 
-pub fn get_comma_separated_exprs(t: &[PartialParsed]) -> Result<Vec<Expression>, SyntaxError> {
-    let mut exprs = Vec::new();
+pub fn get_match_arms(t: &[PartialParsed]) -> Result<Vec<(Pattern, Expression)>, SyntaxError> {
     let commas: Vec<usize> = t
         .iter()
         .enumerate()
         .filter_map(|(i, tkn)| {
-            if matches!(tkn, PartialParsed::Token(Token::Comma)) {
+            if matches!(tkn, PartialParsed::Token(Token::Comma, _)) {
                 Some(i)
             } else {
                 None
             }
         })
         .collect();
+    let mut arms = Vec::with_capacity(commas.len() + 1);
     for i in 0..=commas.len() {
         let start = if i == 0 { 0 } else { commas[i - 1] + 1 };
         let end = if i == commas.len() {
@@ -497,19 +775,79 @@ pub fn get_comma_separated_exprs(t: &[PartialParsed]) -> Result<Vec<Expression>,
         } else {
             commas[i]
         };
-        let expr = get_expr(&t[start..end])?;
-        exprs.push(expr);
+        if start == end && i == commas.len() {
+            break;
+        }
+        let colon = t[start..end]
+            .iter()
+            .position(|tkn| matches!(tkn, PartialParsed::Token(Token::Colon, _)))
+            .ok_or(SyntaxError::from("match arm is missing a ':'"))?;
+        let pattern = get_pattern(&t[start..start + colon])?;
+        let body = get_expr(&t[start + colon + 1..end])?;
+        arms.push((pattern, body));
     }
-    Ok(exprs)
+    Ok(arms)
 }
 
-pub fn get_comma_separated_identifiers(t: &[Token]) -> Result<Vec<String>, SyntaxError> {
-    let mut idents = Vec::new();
+pub fn get_pattern(t: &[PartialParsed]) -> Result<Pattern, SyntaxError> {
+    if let [PartialParsed::Token(Token::Identifier(name), _)] = t {
+        if name == "_" {
+            return Ok(Pattern::Wildcard);
+        }
+    }
+    match get_expr(t)? {
+        Expression::Value(val) => Ok(Pattern::Value(val)),
+        Expression::UnaryOperator(e, op, _) => match *e {
+            Expression::Value(val) => evaluate_unary_op(val, op)
+                .map(Pattern::Value)
+                .map_err(|_| SyntaxError::from("invalid literal in match pattern")),
+            _ => Err("match patterns must be literals or '_'".into()),
+        },
+        _ => Err("match patterns must be literals or '_'".into()),
+    }
+}
+
+/// Parses the arguments of a call. Named arguments like `sep = ", "` have to follow all
+/// positional ones.
+pub fn get_call_args(t: &[PartialParsed]) -> Result<(Vec<Expression>, NamedArgs), SyntaxError> {
+    let mut args = Vec::new();
+    let mut named_args: NamedArgs = Vec::new();
+    let t = strip_trailing_comma(t);
+    if t.is_empty() {
+        return Ok((args, named_args));
+    }
+    for arg in t.split(|tkn| matches!(tkn, PartialParsed::Token(Token::Comma, _))) {
+        match arg {
+            [PartialParsed::Token(Token::Identifier(name), span), PartialParsed::Token(Token::Assign, _), value @ ..] =>
+            {
+                if named_args.iter().any(|(other, _)| other == name) {
+                    return Err(
+                        SyntaxError::from(format!("duplicate argument '{}'", name)).at(*span)
+                    );
+                }
+                named_args.push((name.to_string(), get_expr(value).map_err(|e| e.at(*span))?));
+            }
+            _ if !named_args.is_empty() => {
+                let error = SyntaxError::from("positional argument after named arguments");
+                return Err(match arg.first() {
+                    Some(tkn) => error.at(tkn.span()),
+                    None => error,
+                });
+            }
+            _ => args.push(get_expr(arg)?),
+        }
+    }
+    Ok((args, named_args))
+}
+
+pub fn get_comma_separated_exprs(t: &[PartialParsed]) -> Result<Vec<Expression>, SyntaxError> {
+    let mut exprs = Vec::new();
+    let t = strip_trailing_comma(t);
     let commas: Vec<usize> = t
         .iter()
         .enumerate()
         .filter_map(|(i, tkn)| {
-            if matches!(tkn, Token::Comma) {
+            if matches!(tkn, PartialParsed::Token(Token::Comma, _)) {
                 Some(i)
             } else {
                 None
@@ -523,29 +861,93 @@ pub fn get_comma_separated_identifiers(t: &[Token]) -> Result<Vec<String>, Synta
         } else {
             commas[i]
         };
-        if start + 1 == end {
-            if let Token::Identifier(ref n) = t[start] {
-                idents.push(n.to_string());
+        let expr = get_expr(&t[start..end])?;
+        exprs.push(expr);
+    }
+    Ok(exprs)
+}
+
+pub fn get_binding(t: &PartialParsed) -> Result<Binding, SyntaxError> {
+    match t {
+        PartialParsed::Token(Token::Identifier(var_name), _) => {
+            Ok(Binding::Variable(var_name.to_string()))
+        }
+        PartialParsed::Brackets(b, _) => {
+            let mut names = Vec::new();
+            for (i, tkn) in b.iter().enumerate() {
+                match tkn {
+                    PartialParsed::Token(Token::Identifier(var_name), _) if i % 2 == 0 => {
+                        names.push(var_name.to_string())
+                    }
+                    PartialParsed::Token(Token::Comma, _) if i % 2 == 1 => (),
+                    _ => return Err("invalid destructuring pattern".into()),
+                }
+            }
+            if names.is_empty() {
+                Err("empty destructuring pattern".into())
             } else {
-                return Err("invalid identifier in list".into());
+                Ok(Binding::List(names))
             }
-        } else {
-            return Err("invalid identifier list".into());
         }
+        _ => Err(SyntaxError::from("invalid loop variable").at(t.span())),
     }
-    Ok(idents)
 }
 
-fn find_matching<T>(t: &[Token], matching: T) -> Option<usize>
+/// Parses the parameters of a closure. Parameters like `y: 10` have a default value, which is
+/// used when the argument is left out, and have to follow all parameters without one. A rest
+/// parameter like `...rest` has to come last.
+pub fn get_parameters(t: &[PartialParsed]) -> Result<Vec<Parameter>, SyntaxError> {
+    let mut params: Vec<Parameter> = Vec::new();
+    let t = strip_trailing_comma(t);
+    if t.is_empty() {
+        return Ok(params);
+    }
+    for param in t.split(|tkn| matches!(tkn, PartialParsed::Token(Token::Comma, _))) {
+        let (name, span, default, rest) = match param {
+            [PartialParsed::Token(Token::Identifier(name), span)] => (name, span, None, false),
+            [PartialParsed::Token(Token::Identifier(name), span), PartialParsed::Token(Token::Colon, _), default @ ..] =>
+            {
+                let default = get_expr(default).map_err(|e| e.at(*span))?;
+                (name, span, Some(default), false)
+            }
+            [PartialParsed::Token(Token::Ellipsis, _), PartialParsed::Token(Token::Identifier(name), span)] => {
+                (name, span, None, true)
+            }
+            _ => return Err("invalid parameter list".into()),
+        };
+        if params.last().is_some_and(|p| p.rest) {
+            return Err(SyntaxError::from(format!(
+                "parameter '{}' follows the rest parameter",
+                name
+            ))
+            .at(*span));
+        }
+        if default.is_none() && !rest && params.last().is_some_and(|p| p.default.is_some()) {
+            return Err(SyntaxError::from(format!(
+                "parameter '{}' without a default follows one with a default",
+                name
+            ))
+            .at(*span));
+        }
+        params.push(Parameter {
+            name: name.to_string(),
+            default,
+            rest,
+        });
+    }
+    Ok(params)
+}
+
+fn find_matching<T>(t: &[Spanned<Token>], matching: T) -> Option<usize>
 where
     T: Fn(Option<&Token>) -> bool,
 {
     let mut indent_level = 0;
     for i in 0..t.len() {
-        if indent_level == 0 && matching(t.get(i)) {
+        if indent_level == 0 && matching(t.get(i).map(|tkn| &tkn.node)) {
             return Some(i);
         }
-        match t[i] {
+        match t[i].node {
             Token::OpeningParethesis => indent_level += 1,
             Token::OpeningBrace => indent_level += 1,
             Token::OpeningBracket => indent_level += 1,
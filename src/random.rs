@@ -0,0 +1,55 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A small splitmix64 generator. It is not cryptographically secure, but it is reproducible for
+/// a given seed.
+#[derive(Debug, Clone)]
+pub(crate) struct Rng(u64);
+
+impl Rng {
+    pub(crate) fn new(seed: u64) -> Self {
+        Rng(seed)
+    }
+
+    /// Seeds the generator from the current time.
+    pub(crate) fn from_time() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or_default();
+        Rng(nanos)
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A float in `[0, 1)`.
+    pub(crate) fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// An integer between `min` and `max`, both included. `min` must not be greater than `max`.
+    pub(crate) fn int_between(&mut self, min: i64, max: i64) -> i64 {
+        let span = (max as i128 - min as i128 + 1) as u128;
+        let offset = (self.next_u64() as u128 * span) >> 64;
+        (min as i128 + offset as i128) as i64
+    }
+
+    /// A version 4 UUID such as `"1b4e28ba-2fa1-41d2-883f-0016d3cca427"`.
+    pub(crate) fn uuid(&mut self) -> String {
+        let hi = self.next_u64() & !0xf000 | 0x4000;
+        let lo = self.next_u64() & !(0b11 << 62) | (0b10 << 62);
+        format!(
+            "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+            hi >> 32,
+            hi >> 16 & 0xffff,
+            hi & 0xffff,
+            lo >> 48,
+            lo & 0xffff_ffff_ffff
+        )
+    }
+}
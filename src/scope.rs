@@ -1,50 +1,76 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use crate::*;
 
-pub type Scope = Vec<HashMap<String, VariableValue>>;
+pub type Scope = Vec<ScopeLayer>;
+
+#[derive(Debug, Clone, Default)]
+pub struct ScopeLayer {
+    variables: HashMap<String, VariableValue>,
+    /// The variables of this layer that were defined with `const` and can't be assigned to.
+    constants: HashSet<String>,
+}
 
 pub fn enter_scope(scope: &mut Scope) {
-    scope.push(HashMap::new())
+    scope.push(ScopeLayer::default())
 }
 
 pub fn exit_scope(scope: &mut Scope) {
     scope.pop();
 }
 
+/// The value of a variable, to be changed in place. Constants can't be changed this way either.
 pub fn get_var_from_scope<'a>(
     scope: &'a mut Scope,
     var_name: &str,
 ) -> Result<&'a mut VariableValue, Command> {
-    scope
+    let layer = scope
         .iter_mut()
         .rev()
-        .find_map(|map| map.get_mut(var_name))
-        .ok_or(Command::Error("variable is not defined".into()))
+        .find(|layer| layer.variables.contains_key(var_name))
+        .ok_or(Command::Error("variable is not defined".into()))?;
+    if layer.constants.contains(var_name) {
+        return Err(Command::Error("cannot assign to constant".into()));
+    }
+    Ok(layer
+        .variables
+        .get_mut(var_name)
+        .expect("the layer should contain the variable"))
 }
 
 pub fn get_var_from_scope_cloned(scope: &Scope, var_name: &str) -> Result<VariableValue, Command> {
     scope
         .iter()
         .rev()
-        .find_map(|map| map.get(var_name).cloned())
+        .find_map(|layer| layer.variables.get(var_name).cloned())
         .ok_or(Command::Error("variable is not defined".into()))
 }
 
+/// Defining a variable that already exists in the innermost layer shadows it, even if it was a
+/// constant.
 pub fn define_var_in_scope(
     scope: &mut Scope,
     var_name: &str,
     val: VariableValue,
 ) -> Result<(), Command> {
-    if let Some(_) = scope
+    let layer = scope.last_mut().expect("there should always be a layer");
+    layer.constants.remove(var_name);
+    layer.variables.insert(var_name.to_string(), val);
+    Ok(())
+}
+
+pub fn define_const_in_scope(
+    scope: &mut Scope,
+    var_name: &str,
+    val: VariableValue,
+) -> Result<(), Command> {
+    define_var_in_scope(scope, var_name, val)?;
+    scope
         .last_mut()
         .expect("there should always be a layer")
-        .insert(var_name.to_string(), val)
-    {
-        Err(Command::Error("variable was already defined".into()))
-    } else {
-        Ok(())
-    }
+        .constants
+        .insert(var_name.to_string());
+    Ok(())
 }
 
 pub fn assign_var_in_scope(
@@ -52,10 +78,14 @@ pub fn assign_var_in_scope(
     var_name: &str,
     val: VariableValue,
 ) -> Result<(), Command> {
-    scope
+    let layer = scope
         .iter_mut()
         .rev()
-        .find_map(|map| map.get_mut(var_name))
-        .map(|v| *v = val)
-        .ok_or(Command::Error("".into()))
+        .find(|layer| layer.variables.contains_key(var_name))
+        .ok_or(Command::Error("".into()))?;
+    if layer.constants.contains(var_name) {
+        return Err(Command::Error("cannot assign to constant".into()));
+    }
+    layer.variables.insert(var_name.to_string(), val);
+    Ok(())
 }
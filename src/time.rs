@@ -0,0 +1,151 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::*;
+
+const MS_PER_DAY: i64 = 86_400_000;
+
+/// Milliseconds since the Unix epoch according to the system clock.
+pub(crate) fn system_time_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+/// Renders a UTC timestamp given in milliseconds since the Unix epoch. Supported specifiers are
+/// `%Y` (year), `%m` (month), `%d` (day), `%H` (hour), `%M` (minute), `%S` (second),
+/// `%f` (milliseconds) and `%%`.
+pub(crate) fn format_time(epoch_ms: i64, pattern: &str) -> Result<String, RuntimeError> {
+    let (year, month, day) = civil_from_days(epoch_ms.div_euclid(MS_PER_DAY));
+    let ms_of_day = epoch_ms.rem_euclid(MS_PER_DAY);
+    let mut s = String::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            s.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('Y') => s.push_str(&format!("{:04}", year)),
+            Some('m') => s.push_str(&format!("{:02}", month)),
+            Some('d') => s.push_str(&format!("{:02}", day)),
+            Some('H') => s.push_str(&format!("{:02}", ms_of_day / 3_600_000)),
+            Some('M') => s.push_str(&format!("{:02}", ms_of_day / 60_000 % 60)),
+            Some('S') => s.push_str(&format!("{:02}", ms_of_day / 1000 % 60)),
+            Some('f') => s.push_str(&format!("{:03}", ms_of_day % 1000)),
+            Some('%') => s.push('%'),
+            spec => return Err(invalid_specifier(spec)),
+        }
+    }
+    Ok(s)
+}
+
+/// The inverse of `format_time`: reads `input` according to `pattern` and returns milliseconds
+/// since the Unix epoch. Fields missing from the pattern default to 1970-01-01 00:00:00.000.
+pub(crate) fn parse_time(input: &str, pattern: &str) -> Result<i64, RuntimeError> {
+    let (mut year, mut month, mut day) = (1970, 1, 1);
+    let (mut hour, mut minute, mut second, mut ms) = (0, 0, 0, 0);
+    let mut rest = input;
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            rest = rest.strip_prefix(c).ok_or_else(|| {
+                RuntimeError::from(format!(
+                    "'{}' does not match the pattern '{}'",
+                    input, pattern
+                ))
+            })?;
+            continue;
+        }
+        let (field, digits) = match chars.next() {
+            Some('Y') => (&mut year, 4),
+            Some('m') => (&mut month, 2),
+            Some('d') => (&mut day, 2),
+            Some('H') => (&mut hour, 2),
+            Some('M') => (&mut minute, 2),
+            Some('S') => (&mut second, 2),
+            Some('f') => (&mut ms, 3),
+            Some('%') => {
+                rest = rest.strip_prefix('%').ok_or_else(|| {
+                    RuntimeError::from(format!(
+                        "'{}' does not match the pattern '{}'",
+                        input, pattern
+                    ))
+                })?;
+                continue;
+            }
+            spec => return Err(invalid_specifier(spec)),
+        };
+        let number = rest
+            .get(..digits)
+            .filter(|n| n.chars().all(|d| d.is_ascii_digit()))
+            .ok_or_else(|| {
+                RuntimeError::from(format!(
+                    "'{}' does not match the pattern '{}'",
+                    input, pattern
+                ))
+            })?;
+        *field = number.parse().unwrap();
+        rest = &rest[digits..];
+    }
+    if !rest.is_empty() {
+        return Err(RuntimeError::from(format!(
+            "'{}' does not match the pattern '{}'",
+            input, pattern
+        )));
+    }
+    if !(1..=12).contains(&month)
+        || !(1..=days_in_month(year, month)).contains(&day)
+        || hour > 23
+        || minute > 59
+        || second > 59
+    {
+        return Err(RuntimeError::from(format!(
+            "'{}' is not a valid time",
+            input
+        )));
+    }
+    Ok(days_from_civil(year, month, day) * MS_PER_DAY
+        + ((hour * 60 + minute) * 60 + second) * 1000
+        + ms)
+}
+
+fn invalid_specifier(spec: Option<char>) -> RuntimeError {
+    match spec {
+        Some(c) => RuntimeError::from(format!("invalid time format specifier '%{}'", c)),
+        None => RuntimeError::from("time pattern ends with a lone '%'"),
+    }
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 for a date in the proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400;
+    (if month <= 2 { year + 1 } else { year }, month, day)
+}
@@ -3,7 +3,6 @@ use std::fmt::Display;
 use log::info;
 
 use crate::*;
-use unescaper::unescape;
 
 #[derive(Debug, Clone)]
 pub enum Token {
@@ -24,8 +23,18 @@ pub enum Token {
     Comma,
     Quote,
     Dot,
+    /// `...` before a rest parameter.
+    Ellipsis,
     Colon,
     Operator(Operator),
+    Comment(Comment),
+}
+
+/// A source comment. `trailing` is set when it follows code on the same line.
+#[derive(Debug, Clone)]
+pub struct Comment {
+    pub text: String,
+    pub trailing: bool,
 }
 
 impl Display for Token {
@@ -46,6 +55,7 @@ impl Display for Token {
             Token::VerticalBar => "|".to_string(),
             Token::Identifier(s) => s.to_string(),
             Token::Keyword(Keyword::Let) => "let".to_string(),
+            Token::Keyword(Keyword::Const) => "const".to_string(),
             Token::Keyword(Keyword::Return) => "return".to_string(),
             Token::Keyword(Keyword::Break) => "break".to_string(),
             Token::Keyword(Keyword::Continue) => "continue".to_string(),
@@ -54,13 +64,16 @@ impl Display for Token {
             Token::Keyword(Keyword::If) => "if".to_string(),
             Token::Keyword(Keyword::In) => "in".to_string(),
             Token::Keyword(Keyword::Else) => "else".to_string(),
+            Token::Keyword(Keyword::Match) => "match".to_string(),
             Token::Value(v) => v.to_string(),
             Token::Semicolon => ";".to_string(),
             Token::Dot => ".".to_string(),
+            Token::Ellipsis => "...".to_string(),
             Token::Operator(Operator::Add) => "+".to_string(),
             Token::Operator(Operator::Subtract) => "-".to_string(),
             Token::Operator(Operator::Multiply) => "*".to_string(),
             Token::Operator(Operator::Divide) => "/".to_string(),
+            Token::Operator(Operator::FloorDivide) => "//".to_string(),
             Token::Operator(Operator::Equal) => "==".to_string(),
             Token::Operator(Operator::LessThan) => "<".to_string(),
             Token::Operator(Operator::LessThanOrEqual) => "<=".to_string(),
@@ -68,11 +81,15 @@ impl Display for Token {
             Token::Operator(Operator::GreaterThanOrEqual) => ">=".to_string(),
             Token::Operator(Operator::NotEqual) => "!=".to_string(),
             Token::Operator(Operator::Not) => "!".to_string(),
+            Token::Operator(Operator::BitNot) => "~".to_string(),
             Token::Operator(Operator::Negate) => "-".to_string(),
             Token::Operator(Operator::UnaryPlus) => "+".to_string(),
             Token::Operator(Operator::And) => "&&".to_string(),
             Token::Operator(Operator::Or) => "||".to_string(),
             Token::Operator(Operator::Modulo) => "%".to_string(),
+            Token::Operator(Operator::Range) => "..".to_string(),
+            Token::Operator(Operator::RangeInclusive) => "..=".to_string(),
+            Token::Comment(comment) => format!("# {}", comment.text),
         };
         f.write_str(&stri)
     }
@@ -81,6 +98,7 @@ impl Display for Token {
 #[derive(Debug, Clone, Copy)]
 pub enum Keyword {
     Let,
+    Const,
     While,
     For,
     Return,
@@ -89,17 +107,48 @@ pub enum Keyword {
     In,
     Break,
     Continue,
+    Match,
 }
 
 #[derive(Debug, Clone)]
 pub enum CharToken {
     Char(char),
     Identifier(String),
-    String(String),
+    /// The text between double quotes, or `None` if the program ended before the closing quote.
+    String(Option<String>),
+    /// The text between single quotes, or `None` if the line ended before the closing quote.
+    CharLiteral(Option<String>),
+    Comment(Comment),
 }
 
-pub fn tokenize(program: &str) -> Result<Vec<Token>, SyntaxError> {
-    let char_tokens = preprocess(program.chars().collect());
+/// A position in the source. Lines and columns start at 1 and columns count characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// A value together with the position in the source where it starts.
+#[derive(Debug, Clone)]
+pub struct Spanned<T> {
+    pub node: T,
+    pub span: Span,
+}
+
+pub fn tokenize(program: &str) -> Result<Vec<Spanned<Token>>, SyntaxError> {
+    tokenize_program(program, false)
+}
+
+/// Like `tokenize`, but keeps comments as `Token::Comment` so they can be re-emitted.
+pub fn tokenize_with_comments(program: &str) -> Result<Vec<Spanned<Token>>, SyntaxError> {
+    tokenize_program(program, true)
+}
+
+fn tokenize_program(
+    program: &str,
+    keep_comments: bool,
+) -> Result<Vec<Spanned<Token>>, SyntaxError> {
+    let char_tokens = preprocess(with_spans(program), keep_comments);
     info!("initial tokens: {:?}", char_tokens);
 
     let mapped_tokens = map_tokens(char_tokens)?;
@@ -111,69 +160,201 @@ pub fn tokenize(program: &str) -> Result<Vec<Token>, SyntaxError> {
     Ok(merged_tokens)
 }
 
-fn preprocess(tokens: Vec<char>) -> Vec<CharToken> {
+fn spanned<T>(node: T, span: Span) -> Spanned<T> {
+    Spanned { node, span }
+}
+
+/// Whether a literal read so far ends in an unescaped backslash, so the next quote belongs to it.
+fn ends_in_escape(s: &str) -> bool {
+    s.chars().rev().take_while(|c| *c == '\\').count() % 2 == 1
+}
+
+fn with_spans(program: &str) -> Vec<(char, Span)> {
+    let mut span = Span { line: 1, column: 1 };
+    program
+        .chars()
+        .map(|c| {
+            let char_span = span;
+            if c == '\n' {
+                span = Span {
+                    line: span.line + 1,
+                    column: 1,
+                };
+            } else {
+                span.column += 1;
+            }
+            (c, char_span)
+        })
+        .collect()
+}
+
+fn preprocess(tokens: Vec<(char, Span)>, keep_comments: bool) -> Vec<Spanned<CharToken>> {
     let mut tokens_with_strings = Vec::new();
-    let mut cur_string: Option<String> = None;
-    for i in 0..tokens.len() {
-        cur_string = match tokens[i] {
+    let mut cur_string: Option<Spanned<String>> = None;
+    let mut cur_char: Option<Spanned<String>> = None;
+    let mut in_comment = false;
+    for (c, span) in tokens {
+        if let Some(mut literal) = cur_char.take() {
+            match c {
+                '\'' if !ends_in_escape(&literal.node) => tokens_with_strings.push(spanned(
+                    CharToken::CharLiteral(Some(literal.node)),
+                    literal.span,
+                )),
+                '\n' => {
+                    tokens_with_strings.push(spanned(CharToken::CharLiteral(None), literal.span));
+                    tokens_with_strings.push(spanned(CharToken::Char(c), span));
+                }
+                c => {
+                    literal.node.push(c);
+                    cur_char = Some(literal);
+                }
+            }
+            continue;
+        }
+        // quotes in comments don't start string or char literals
+        if cur_string.is_none() {
+            match c {
+                '#' => in_comment = !in_comment,
+                '\n' => in_comment = false,
+                '\'' if !in_comment => {
+                    cur_char = Some(spanned(String::new(), span));
+                    continue;
+                }
+                _ => (),
+            }
+            if in_comment {
+                tokens_with_strings.push(spanned(CharToken::Char(c), span));
+                continue;
+            }
+        }
+        cur_string = match c {
             '"' => match cur_string {
+                Some(mut str) if ends_in_escape(&str.node) => {
+                    str.node.push(c);
+                    Some(str)
+                }
                 Some(str) => {
-                    tokens_with_strings.push(CharToken::String(str.clone()));
+                    tokens_with_strings.push(spanned(CharToken::String(Some(str.node)), str.span));
                     None
                 }
-                None => Some(String::new()),
+                None => Some(spanned(String::new(), span)),
             },
             c => match cur_string {
                 Some(mut str) => {
-                    str.push(c);
+                    str.node.push(c);
                     Some(str)
                 }
                 None => {
-                    tokens_with_strings.push(CharToken::Char(c));
+                    tokens_with_strings.push(spanned(CharToken::Char(c), span));
                     None
                 }
             },
         }
     }
 
+    if let Some(literal) = cur_char {
+        tokens_with_strings.push(spanned(CharToken::CharLiteral(None), literal.span));
+    }
+    if let Some(str) = cur_string {
+        tokens_with_strings.push(spanned(CharToken::String(None), str.span));
+    }
+
     let mut tokens_without_comments = Vec::new();
-    let mut cur_comment = false;
-    for i in 0..tokens_with_strings.len() {
-        match &tokens_with_strings[i] {
-            CharToken::Char('#') => cur_comment = !cur_comment,
-            CharToken::Char('\n') => cur_comment = false,
-            tkn => {
-                if !cur_comment {
-                    tokens_without_comments.push(tkn.clone())
+    let mut cur_comment: Option<Spanned<Comment>> = None;
+    let mut line_has_code = false;
+    let end_comment = |comment: Spanned<Comment>, tokens: &mut Vec<Spanned<CharToken>>| {
+        if keep_comments {
+            tokens.push(spanned(
+                CharToken::Comment(Comment {
+                    text: comment.node.text.trim().to_string(),
+                    ..comment.node
+                }),
+                comment.span,
+            ));
+        }
+    };
+    for tkn in tokens_with_strings {
+        match (&tkn.node, &mut cur_comment) {
+            (CharToken::Char('#'), Some(_)) => {
+                end_comment(cur_comment.take().unwrap(), &mut tokens_without_comments)
+            }
+            (CharToken::Char('#'), None) => {
+                cur_comment = Some(spanned(
+                    Comment {
+                        text: String::new(),
+                        trailing: line_has_code,
+                    },
+                    tkn.span,
+                ))
+            }
+            (CharToken::Char('\n'), _) => {
+                if let Some(comment) = cur_comment.take() {
+                    end_comment(comment, &mut tokens_without_comments);
+                }
+                line_has_code = false;
+            }
+            (CharToken::Char(c), Some(comment)) => comment.node.text.push(*c),
+            (node, _) => {
+                if !matches!(node, CharToken::Char(c) if c.is_whitespace()) {
+                    line_has_code = true;
                 }
+                tokens_without_comments.push(tkn)
             }
         }
     }
+    if let Some(comment) = cur_comment {
+        end_comment(comment, &mut tokens_without_comments);
+    }
 
     let mut tokens_with_identifiers = Vec::new();
-    let mut cur_identifier = None;
+    let mut cur_identifier: Option<Spanned<String>> = None;
     for i in 0..tokens_without_comments.len() {
-        match &tokens_without_comments[i] {
+        let tkn = &tokens_without_comments[i];
+        match tkn.node {
             CharToken::Char(c) => {
-                if c.is_alphanumeric() || *c == '_' {
-                    cur_identifier.get_or_insert(String::new()).push(*c);
-                } else if let Some(s) = cur_identifier {
-                    tokens_with_identifiers.push(CharToken::Identifier(s));
-                    cur_identifier = None;
-                    tokens_with_identifiers.push(CharToken::Char(*c));
+                let is_decimal_point = c == '.'
+                    && cur_identifier
+                        .as_ref()
+                        .is_some_and(|s| s.node.chars().all(|d| d.is_ascii_digit()))
+                    && matches!(
+                        tokens_without_comments.get(i + 1).map(|t| &t.node),
+                        Some(CharToken::Char(d)) if d.is_ascii_digit()
+                    );
+                // `sort!(list)` calls the mutating variant of `sort`
+                let is_mutating_suffix = c == '!'
+                    && cur_identifier.is_some()
+                    && matches!(
+                        tokens_without_comments.get(i + 1).map(|t| &t.node),
+                        Some(CharToken::Char('('))
+                    );
+                if c.is_alphanumeric() || c == '_' || is_decimal_point || is_mutating_suffix {
+                    cur_identifier
+                        .get_or_insert(spanned(String::new(), tkn.span))
+                        .node
+                        .push(c);
                 } else {
-                    tokens_with_identifiers.push(CharToken::Char(*c));
+                    if let Some(s) = cur_identifier.take() {
+                        tokens_with_identifiers
+                            .push(spanned(CharToken::Identifier(s.node), s.span));
+                    }
+                    tokens_with_identifiers.push(tkn.clone());
                 }
             }
-            tkn => {
+            _ => {
+                if let Some(s) = cur_identifier.take() {
+                    tokens_with_identifiers.push(spanned(CharToken::Identifier(s.node), s.span));
+                }
                 tokens_with_identifiers.push(tkn.clone());
             }
         }
     }
+    if let Some(s) = cur_identifier {
+        tokens_with_identifiers.push(spanned(CharToken::Identifier(s.node), s.span));
+    }
 
     let tokens_without_whitespace = tokens_with_identifiers
         .into_iter()
-        .filter(|tkn| match tkn {
+        .filter(|tkn| match tkn.node {
             CharToken::Char(c) => !c.is_whitespace(),
             _ => true,
         })
@@ -182,20 +363,105 @@ fn preprocess(tokens: Vec<char>) -> Vec<CharToken> {
     tokens_without_whitespace
 }
 
-fn map_tokens(tokens: Vec<CharToken>) -> Result<Vec<Token>, SyntaxError> {
-    tokens
-        .into_iter()
-        .filter_map(|x| match x {
-            CharToken::Char(c) => Some(map_char_token(c, x)),
-            CharToken::Identifier(s) => Some(map_string_token(s)),
-            CharToken::String(s) => {
-                let unescaped_s = unescape(&s)
-                    .map_err(|e| SyntaxError(format!("couldn't unescape '{}': {}", s, e)));
-                let return_val = unescaped_s.map(|rs| Token::Value(VariableValue::String(rs)));
-                Some(return_val)
+fn map_tokens(tokens: Vec<Spanned<CharToken>>) -> Result<Vec<Spanned<Token>>, SyntaxError> {
+    tokens.into_iter().map(map_token).collect()
+}
+
+fn map_token(x: Spanned<CharToken>) -> Result<Spanned<Token>, SyntaxError> {
+    match x.node {
+        CharToken::Char(c) => map_char_token(c, x.node.clone()),
+        CharToken::Identifier(s) => map_string_token(s),
+        CharToken::String(Some(s)) => {
+            string_literal(&s).map(|s| Token::Value(VariableValue::String(s)))
+        }
+        CharToken::String(None) => Err("unterminated string literal".into()),
+        CharToken::CharLiteral(Some(s)) => {
+            char_literal(&s).map(|c| Token::Value(VariableValue::String(c.to_string())))
+        }
+        CharToken::CharLiteral(None) => Err("unterminated char literal".into()),
+        CharToken::Comment(comment) => Ok(Token::Comment(comment)),
+    }
+    .map(|node| Spanned { node, span: x.span })
+    .map_err(|e| e.at(x.span))
+}
+
+/// Tokenizes as much of `program` as possible, keeping comments. A piece of source that is not a
+/// valid token, such as an unterminated string literal, is returned as the `CharToken` it was
+/// read as instead of failing the whole program, so editors can still work with the rest.
+pub(crate) fn tokenize_leniently(program: &str) -> Vec<Spanned<Result<Token, CharToken>>> {
+    let mut tokens = Vec::new();
+    let mut valid = Vec::new();
+    let flush = |valid: &mut Vec<Spanned<Token>>, tokens: &mut Vec<_>| {
+        tokens.extend(
+            token_merger(std::mem::take(valid))
+                .into_iter()
+                .map(|t| spanned(Ok(t.node), t.span)),
+        )
+    };
+    for tkn in preprocess(with_spans(program), true) {
+        match map_token(tkn.clone()) {
+            Ok(token) => valid.push(token),
+            Err(_) => {
+                flush(&mut valid, &mut tokens);
+                tokens.push(spanned(Err(tkn.node), tkn.span));
             }
-        })
-        .collect()
+        }
+    }
+    flush(&mut valid, &mut tokens);
+    tokens
+}
+
+/// The character an escape sequence `\c` stands for. Supported escapes are `\n`, `\t`, `\r`,
+/// `\0`, `\\`, `\'` and `\"`.
+fn escape(c: char, literal: &str) -> Result<char, SyntaxError> {
+    match c {
+        'n' => Ok('\n'),
+        't' => Ok('\t'),
+        'r' => Ok('\r'),
+        '0' => Ok('\0'),
+        '\\' | '\'' | '"' => Ok(c),
+        c => Err(SyntaxError::from(format!(
+            "unknown escape '\\{}' in {}",
+            c, literal
+        ))),
+    }
+}
+
+/// Reads the text between the quotes of a string literal, replacing its escape sequences.
+fn string_literal(s: &str) -> Result<String, SyntaxError> {
+    let mut result = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        result.push(match c {
+            '\\' => match chars.next() {
+                Some(c) => escape(c, "string literal")?,
+                None => return Err("unterminated string literal".into()),
+            },
+            c => c,
+        });
+    }
+    Ok(result)
+}
+
+/// Reads the text between the quotes of a char literal, which must be a single character or
+/// an escape sequence.
+fn char_literal(s: &str) -> Result<char, SyntaxError> {
+    let mut chars = s.chars();
+    let c = match chars.next() {
+        None => return Err("empty char literal".into()),
+        Some('\\') => match chars.next() {
+            Some(c) => escape(c, "char literal")?,
+            None => return Err("unterminated char literal".into()),
+        },
+        Some(c) => c,
+    };
+    match chars.next() {
+        None => Ok(c),
+        Some(_) => Err(SyntaxError::from(format!(
+            "char literal '{}' must be a single character",
+            s
+        ))),
+    }
 }
 
 fn map_char_token(c: char, token: CharToken) -> Result<Token, SyntaxError> {
@@ -209,6 +475,7 @@ fn map_char_token(c: char, token: CharToken) -> Result<Token, SyntaxError> {
         '<' => Ok(Token::Operator(Operator::LessThan)),
         '>' => Ok(Token::Operator(Operator::GreaterThan)),
         '!' => Ok(Token::Operator(Operator::Not)),
+        '~' => Ok(Token::Operator(Operator::BitNot)),
         ';' => Ok(Token::Semicolon),
         ',' => Ok(Token::Comma),
         ':' => Ok(Token::Colon),
@@ -226,16 +493,17 @@ fn map_char_token(c: char, token: CharToken) -> Result<Token, SyntaxError> {
             if chr.is_ascii_alphabetic() {
                 Ok(Token::Identifier(chr.to_string()))
             } else if let Some(d) = chr.to_digit(10) {
-                Ok(Token::Value(VariableValue::Number(d as i32)))
+                Ok(Token::Value(VariableValue::Int(d as i64)))
             } else {
-                Err(SyntaxError(format!("Invalid Token: {:?}", token)))
+                Err(SyntaxError::from(format!("Invalid Token: {:?}", token)))
             }
         }
     }
 }
-fn map_string_token(s: String) -> Result<Token, SyntaxError> {
+pub(crate) fn map_string_token(s: String) -> Result<Token, SyntaxError> {
     match s.as_str() {
         "let" => Ok(Token::Keyword(Keyword::Let)),
+        "const" => Ok(Token::Keyword(Keyword::Const)),
         "while" => Ok(Token::Keyword(Keyword::While)),
         "for" => Ok(Token::Keyword(Keyword::For)),
         "return" => Ok(Token::Keyword(Keyword::Return)),
@@ -244,11 +512,17 @@ fn map_string_token(s: String) -> Result<Token, SyntaxError> {
         "if" => Ok(Token::Keyword(Keyword::If)),
         "in" => Ok(Token::Keyword(Keyword::In)),
         "else" => Ok(Token::Keyword(Keyword::Else)),
+        "match" => Ok(Token::Keyword(Keyword::Match)),
         "true" => Ok(Token::Value(VariableValue::Boolean(true))),
         "false" => Ok(Token::Value(VariableValue::Boolean(false))),
+        "none" => Ok(Token::Value(VariableValue::None)),
         str => {
             if let Ok(num) = str::parse(str) {
-                Ok(Token::Value(VariableValue::Number(num)))
+                Ok(Token::Value(VariableValue::Int(num)))
+            } else if str.starts_with(|c: char| c.is_ascii_digit()) && str.contains('.') {
+                str::parse(str)
+                    .map(|num| Token::Value(VariableValue::Float(num)))
+                    .map_err(|_| SyntaxError::from(format!("Invalid number literal: {}", str)))
             } else {
                 Ok(Token::Identifier(str.to_owned()))
             }
@@ -256,91 +530,90 @@ fn map_string_token(s: String) -> Result<Token, SyntaxError> {
     }
 }
 
-fn token_merger(tokens: Vec<Token>) -> Vec<Token> {
-    let mut new_tokens = Vec::new();
+fn token_merger(tokens: Vec<Spanned<Token>>) -> Vec<Spanned<Token>> {
+    let mut new_tokens: Vec<Spanned<Token>> = Vec::new();
     for i in 0..tokens.len() {
-        let (prev, cur) = (new_tokens.last(), tokens.get(i));
+        let (prev, cur) = (new_tokens.last().map(|t| &t.node), tokens.get(i));
         if let Some(cur_tkn) = cur {
             match prev {
-                Some(Token::Assign) => match cur_tkn {
+                Some(Token::Assign) => match &cur_tkn.node {
                     Token::Assign => {
-                        new_tokens.pop();
-                        new_tokens.push(Token::Operator(Operator::Equal));
+                        replace_last(&mut new_tokens, Token::Operator(Operator::Equal));
                     }
                     _ => new_tokens.push(cur_tkn.clone()),
                 },
-                Some(Token::Operator(Operator::Not)) => match cur_tkn {
+                Some(Token::Operator(Operator::Not)) => match &cur_tkn.node {
                     Token::Assign => {
-                        new_tokens.pop();
-                        new_tokens.push(Token::Operator(Operator::NotEqual));
+                        replace_last(&mut new_tokens, Token::Operator(Operator::NotEqual));
                     }
                     Token::Operator(Operator::Not) => {
                         new_tokens.pop();
                     }
                     _ => new_tokens.push(cur_tkn.clone()),
                 },
-                Some(Token::Operator(Operator::LessThan)) => match cur_tkn {
+                Some(Token::Operator(Operator::LessThan)) => match &cur_tkn.node {
                     Token::Assign => {
-                        new_tokens.pop();
-                        new_tokens.push(Token::Operator(Operator::LessThanOrEqual));
+                        replace_last(&mut new_tokens, Token::Operator(Operator::LessThanOrEqual));
                     }
                     _ => new_tokens.push(cur_tkn.clone()),
                 },
-                Some(Token::Operator(Operator::GreaterThan)) => match cur_tkn {
+                Some(Token::Operator(Operator::GreaterThan)) => match &cur_tkn.node {
                     Token::Assign => {
-                        new_tokens.pop();
-                        new_tokens.push(Token::Operator(Operator::GreaterThanOrEqual));
+                        replace_last(
+                            &mut new_tokens,
+                            Token::Operator(Operator::GreaterThanOrEqual),
+                        );
                     }
                     _ => new_tokens.push(cur_tkn.clone()),
                 },
-                Some(Token::Operator(Operator::Subtract)) => match cur_tkn {
-                    Token::Operator(Operator::Subtract) => {
-                        new_tokens.pop();
-                        new_tokens.push(Token::Operator(Operator::Add));
-                    }
-                    Token::Operator(Operator::Add) => {
-                        new_tokens.pop();
-                        new_tokens.push(Token::Operator(Operator::Subtract));
+                Some(Token::Operator(Operator::Subtract)) => match &cur_tkn.node {
+                    Token::Assign => {
+                        replace_last(&mut new_tokens, Token::OperatorAssign(Operator::Subtract));
                     }
+                    _ => new_tokens.push(cur_tkn.clone()),
+                },
+                Some(Token::Operator(Operator::Add)) => match &cur_tkn.node {
                     Token::Assign => {
-                        new_tokens.pop();
-                        new_tokens.push(Token::OperatorAssign(Operator::Subtract));
+                        replace_last(&mut new_tokens, Token::OperatorAssign(Operator::Add));
                     }
                     _ => new_tokens.push(cur_tkn.clone()),
                 },
-                Some(Token::Operator(Operator::Add)) => match cur_tkn {
-                    Token::Operator(Operator::Subtract) => {
-                        new_tokens.pop();
-                        new_tokens.push(Token::Operator(Operator::Subtract));
+                Some(Token::Operator(Operator::Multiply)) => match &cur_tkn.node {
+                    Token::Assign => {
+                        replace_last(&mut new_tokens, Token::OperatorAssign(Operator::Multiply));
                     }
-                    Token::Operator(Operator::Add) => {
-                        new_tokens.pop();
-                        new_tokens.push(Token::Operator(Operator::Add));
+                    _ => new_tokens.push(cur_tkn.clone()),
+                },
+                Some(Token::Operator(Operator::Divide)) => match &cur_tkn.node {
+                    Token::Operator(Operator::Divide) => {
+                        replace_last(&mut new_tokens, Token::Operator(Operator::FloorDivide));
                     }
-                    Token::Assign => {
-                        new_tokens.pop();
-                        new_tokens.push(Token::OperatorAssign(Operator::Add));
+                    _ => new_tokens.push(cur_tkn.clone()),
+                },
+                Some(Token::Dot) => match &cur_tkn.node {
+                    Token::Dot => {
+                        replace_last(&mut new_tokens, Token::Operator(Operator::Range));
                     }
                     _ => new_tokens.push(cur_tkn.clone()),
                 },
-                Some(Token::Operator(Operator::Multiply)) => match cur_tkn {
+                Some(Token::Operator(Operator::Range)) => match &cur_tkn.node {
+                    Token::Dot => {
+                        replace_last(&mut new_tokens, Token::Ellipsis);
+                    }
                     Token::Assign => {
-                        new_tokens.pop();
-                        new_tokens.push(Token::OperatorAssign(Operator::Multiply));
+                        replace_last(&mut new_tokens, Token::Operator(Operator::RangeInclusive));
                     }
                     _ => new_tokens.push(cur_tkn.clone()),
                 },
-                Some(Token::Ampersand) => match cur_tkn {
+                Some(Token::Ampersand) => match &cur_tkn.node {
                     Token::Ampersand => {
-                        new_tokens.pop();
-                        new_tokens.push(Token::Operator(Operator::And));
+                        replace_last(&mut new_tokens, Token::Operator(Operator::And));
                     }
                     _ => new_tokens.push(cur_tkn.clone()),
                 },
-                Some(Token::VerticalBar) => match cur_tkn {
+                Some(Token::VerticalBar) => match &cur_tkn.node {
                     Token::VerticalBar => {
-                        new_tokens.pop();
-                        new_tokens.push(Token::Operator(Operator::Or));
+                        replace_last(&mut new_tokens, Token::Operator(Operator::Or));
                     }
                     _ => new_tokens.push(cur_tkn.clone()),
                 },
@@ -350,3 +623,10 @@ fn token_merger(tokens: Vec<Token>) -> Vec<Token> {
     }
     new_tokens
 }
+
+/// Replaces the last token with one merged from it and the following token, keeping its span.
+fn replace_last(tokens: &mut [Spanned<Token>], node: Token) {
+    if let Some(last) = tokens.last_mut() {
+        last.node = node;
+    }
+}
@@ -1,13 +1,22 @@
-use std::fmt::Display;
+use std::{cmp::Ordering, fmt::Display};
 
 use crate::*;
 
+/// A call that starts with less native stack left than this continues on a new stack of
+/// `CALL_STACK_SIZE` bytes. Debug builds use tens of kilobytes of stack per call.
+const CALL_RED_ZONE: usize = 1024 * 1024;
+const CALL_STACK_SIZE: usize = 16 * 1024 * 1024;
+/// The most integers a range used as a value, or built by `range` and `downto`, is built into.
+/// Loops walk `a..b` without building it, so it isn't limited there.
+pub(crate) const MAX_RANGE_LEN: i128 = 1 << 24;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum Operator {
     Add,
     Subtract,
     Multiply,
     Divide,
+    FloorDivide,
     NotEqual,
     Equal,
     LessThan,
@@ -17,21 +26,28 @@ pub enum Operator {
     And,
     Or,
     Not,
+    BitNot,
     Negate,
     UnaryPlus,
     Modulo,
+    Range,
+    RangeInclusive,
 }
 
 impl Operator {
     pub fn precedence(&self) -> u32 {
         match self {
             Operator::Not => 10,
+            Operator::BitNot => 10,
             Operator::Negate => 8,
             Operator::UnaryPlus => 8,
             Operator::Multiply => 6,
             Operator::Divide => 6,
+            Operator::FloorDivide => 6,
             Operator::Add => 4,
             Operator::Subtract => 4,
+            Operator::Range => 3,
+            Operator::RangeInclusive => 3,
             Operator::LessThan => 2,
             Operator::GreaterThan => 2,
             Operator::Equal => 2,
@@ -47,19 +63,26 @@ impl Operator {
 
 #[derive(Debug, Clone)]
 pub enum VariableValue {
-    Number(i32),
+    Int(i64),
+    Float(f64),
     Boolean(bool),
     String(String),
     Unit,
-    Function(Vec<String>, Box<Expression>),
+    None,
+    Function(Vec<Parameter>, Box<Expression>),
     List(Vec<VariableValue>),
-    Object(HashMap<String, VariableValue>),
+    /// Fields in the order they were first defined, which is the order they are visited in.
+    Object(IndexMap<String, VariableValue>),
+    Bytes(Vec<u8>),
+    /// A list, object or bytes value that can't be changed in place, made by `freeze`.
+    Frozen(Box<VariableValue>),
 }
 
 impl Display for VariableValue {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let stri = match self {
             VariableValue::Unit => "()".to_string(),
+            VariableValue::None => "none".to_string(),
             VariableValue::Object(m) => {
                 let mut s = String::new();
                 s.push('{');
@@ -74,10 +97,28 @@ impl Display for VariableValue {
                 s.push('}');
                 s
             }
-            VariableValue::Number(n) => n.to_string(),
+            VariableValue::Int(n) => n.to_string(),
+            VariableValue::Float(n) => {
+                let s = n.to_string();
+                if s.contains(['.', 'e', 'i', 'N']) {
+                    s
+                } else {
+                    format!("{}.0", s)
+                }
+            }
             VariableValue::Boolean(b) => b.to_string(),
             VariableValue::String(s) => format!("\"{}\"", s),
-            VariableValue::Function(args, expr) => format!("{:?} -> {:?}", args, expr),
+            VariableValue::Function(args, _) => format!(
+                "<fn({})>",
+                args.iter()
+                    .map(|arg| if arg.rest {
+                        format!("...{}", arg.name)
+                    } else {
+                        arg.name.clone()
+                    })
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
             VariableValue::List(list) => {
                 let mut s = String::new();
                 s.push('[');
@@ -91,6 +132,15 @@ impl Display for VariableValue {
                 s.push(']');
                 s
             }
+            VariableValue::Bytes(bytes) => format!(
+                "bytes[{}]",
+                bytes
+                    .iter()
+                    .map(|b| b.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            VariableValue::Frozen(value) => value.to_string(),
         };
         f.write_str(&stri)
     }
@@ -99,23 +149,70 @@ impl Display for VariableValue {
 impl VariableValue {
     pub fn call(
         &self,
-        scope: &mut Scope,
+        ctx: &mut Context,
+        params: Vec<VariableValue>,
+    ) -> Result<VariableValue, Command> {
+        ctx.enter_call().map_err(Command::Error)?;
+        // calls get more native stack on the heap when they run low, so the call depth limit
+        // is reached before the stack of the host's thread overflows
+        let result = stacker::maybe_grow(CALL_RED_ZONE, CALL_STACK_SIZE, || {
+            self.call_in_new_scope(ctx, params)
+        });
+        ctx.exit_call();
+        result
+    }
+
+    fn call_in_new_scope(
+        &self,
+        ctx: &mut Context,
         params: Vec<VariableValue>,
     ) -> Result<VariableValue, Command> {
         let result = match self {
             VariableValue::Function(args, body) => {
-                enter_scope(scope);
-                for (var, val) in args.iter().zip(params.iter()) {
-                    define_var_by_val(scope, var, val.clone())?;
+                // builtins are given all arguments and check them themselves
+                let is_builtin = matches!(**body, Expression::BuiltinFunctionCall(_, _, _));
+                let required = args
+                    .iter()
+                    .filter(|arg| arg.default.is_none() && !arg.rest)
+                    .count();
+                let variadic = args.last().is_some_and(|arg| arg.rest);
+                let accepted = if variadic {
+                    params.len() >= required
+                } else {
+                    (required..=args.len()).contains(&params.len())
+                };
+                if !is_builtin && !accepted {
+                    let expected = if variadic {
+                        format!("at least {}", required)
+                    } else if required == args.len() {
+                        required.to_string()
+                    } else {
+                        format!("{} to {}", required, args.len())
+                    };
+                    return Err(Command::Error(
+                        format!(
+                            "function expected {} argument{}, got {}",
+                            expected,
+                            if variadic && required == 1 || !variadic && args.len() == 1 {
+                                ""
+                            } else {
+                                "s"
+                            },
+                            params.len()
+                        )
+                        .into(),
+                    ));
                 }
-                define_var_by_val(scope, "self", self.clone())?;
-                match match *body.clone() {
-                    Expression::BuiltinFunctionCall(name, target, _) => {
-                        let new_body = Expression::BuiltinFunctionCall(name, target, params);
-                        eval_expr(scope, &new_body)
-                    }
-                    any_body => eval_expr(scope, &any_body),
-                } {
+                enter_scope(&mut ctx.scope);
+                match bind_params(ctx, args, &params)
+                    .and_then(|_| define_var_by_val(ctx, "self", self.clone()))
+                    .and_then(|_| match *body.clone() {
+                        Expression::BuiltinFunctionCall(name, target, _) => {
+                            let new_body = Expression::BuiltinFunctionCall(name, target, params);
+                            eval_expr(ctx, &new_body)
+                        }
+                        any_body => eval_expr(ctx, &any_body),
+                    }) {
                     Ok(val) => Ok(val),
                     Err(command) => match command {
                         Command::Return(val) => Ok(val),
@@ -129,158 +226,376 @@ impl VariableValue {
                     },
                 }
             }
-            _ => Err(Command::Error(
-                format!("variable {} is not callable", self).into(),
-            )),
+            _ => {
+                return Err(Command::Error(
+                    format!("variable {} is not callable", self).into(),
+                ))
+            }
         };
-        exit_scope(scope);
+        exit_scope(&mut ctx.scope);
         result
     }
 
     pub fn get_type(&self) -> String {
         match self {
             VariableValue::Boolean(_) => "Boolean",
-            VariableValue::Number(_) => "Number",
+            VariableValue::Int(_) => "Int",
+            VariableValue::Float(_) => "Float",
             VariableValue::List(_) => "List",
             VariableValue::Function(_, _) => "Function",
             VariableValue::Unit => "Unit",
+            VariableValue::None => "None",
             VariableValue::String(_) => "String",
             VariableValue::Object(_) => "Object",
+            VariableValue::Bytes(_) => "Bytes",
+            VariableValue::Frozen(value) => return value.get_type(),
         }
         .to_string()
     }
+
+    /// Makes a list, object or bytes value immutable. Values inside it stay as they are. Other
+    /// values can't be changed in place anyway and are returned unchanged.
+    pub fn freeze(self) -> VariableValue {
+        match self {
+            value @ (Self::List(_) | Self::Object(_) | Self::Bytes(_)) => {
+                Self::Frozen(Box::new(value))
+            }
+            value => value,
+        }
+    }
+
+    /// Freezes a value and every list and object inside it.
+    pub fn deep_freeze(self) -> VariableValue {
+        match self.thaw() {
+            Self::List(list) => {
+                Self::List(list.into_iter().map(Self::deep_freeze).collect()).freeze()
+            }
+            Self::Object(object) => Self::Object(
+                object
+                    .into_iter()
+                    .map(|(key, value)| (key, value.deep_freeze()))
+                    .collect(),
+            )
+            .freeze(),
+            value => value.freeze(),
+        }
+    }
+
+    /// Whether the value can't be changed in place, which holds for everything but lists,
+    /// objects and bytes that aren't frozen.
+    pub fn is_frozen(&self) -> bool {
+        !matches!(self, Self::List(_) | Self::Object(_) | Self::Bytes(_))
+    }
+
+    /// The value without the marker `freeze` puts on it, for reading it.
+    pub fn thaw(self) -> VariableValue {
+        match self {
+            Self::Frozen(value) => *value,
+            value => value,
+        }
+    }
+
+    /// Thaws a value and every list and object inside it, undoing `deep_freeze`.
+    pub fn deep_thaw(self) -> VariableValue {
+        match self.thaw() {
+            Self::List(list) => Self::List(list.into_iter().map(Self::deep_thaw).collect()),
+            Self::Object(object) => Self::Object(
+                object
+                    .into_iter()
+                    .map(|(key, value)| (key, value.deep_thaw()))
+                    .collect(),
+            ),
+            value => value,
+        }
+    }
+
+    /// Like `thaw`, but looks inside a borrowed value.
+    pub fn thawed(&self) -> &VariableValue {
+        match self {
+            Self::Frozen(value) => value,
+            value => value,
+        }
+    }
+
+    /// Whether the value counts as true in a condition when conditions aren't required to be
+    /// booleans. `false`, zero, `none` and empty strings, lists, objects and bytes are false,
+    /// everything else is true.
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Self::Boolean(b) => *b,
+            Self::Int(n) => *n != 0,
+            Self::Float(n) => *n != 0.0,
+            Self::String(s) => !s.is_empty(),
+            Self::List(list) => !list.is_empty(),
+            Self::Object(object) => !object.is_empty(),
+            Self::Bytes(bytes) => !bytes.is_empty(),
+            Self::None => false,
+            Self::Frozen(value) => value.is_truthy(),
+            Self::Unit | Self::Function(_, _) => true,
+        }
+    }
+
+    /// Widens a pair of numbers to floats, so mixed arithmetic promotes to `Float`.
+    fn as_floats(a: &VariableValue, b: &VariableValue) -> Option<(f64, f64)> {
+        match (a, b) {
+            (Self::Int(na), Self::Int(nb)) => Some((*na as f64, *nb as f64)),
+            (Self::Int(na), Self::Float(nb)) => Some((*na as f64, *nb)),
+            (Self::Float(na), Self::Int(nb)) => Some((*na, *nb as f64)),
+            (Self::Float(na), Self::Float(nb)) => Some((*na, *nb)),
+            _ => None,
+        }
+    }
+
     pub fn add(a: VariableValue, b: VariableValue) -> Result<VariableValue, RuntimeError> {
         match (a, b) {
-            (Self::Number(na), Self::Number(nb)) => Ok(VariableValue::Number(na + nb)),
+            (Self::Int(na), Self::Int(nb)) => checked(na.checked_add(nb), na, "+", nb),
             (Self::String(na), Self::String(nb)) => Ok(VariableValue::String(na + &nb)),
             (Self::List(mut na), Self::List(mut nb)) => {
                 na.append(&mut nb);
                 Ok(VariableValue::List(na))
             }
-            (Self::List(mut na), other) => {
-                na.push(other);
-                Ok(VariableValue::List(na))
+            (Self::Object(mut na), Self::Object(nb)) => {
+                na.extend(nb);
+                Ok(VariableValue::Object(na))
             }
-            (x, y) => Err(RuntimeError(format!(
-                "Addition between {} and {} is not implemented!",
-                x, y
-            ))),
+            (x, y) => match Self::as_floats(&x, &y) {
+                Some((na, nb)) => checked_float(na + nb, &x, "+", &y),
+                None => Err(RuntimeError::from(format!(
+                    "Addition between {} and {} is not implemented!",
+                    x, y
+                ))),
+            },
         }
     }
 
     pub fn subtract(a: VariableValue, b: VariableValue) -> Result<VariableValue, RuntimeError> {
         match (a, b) {
-            (Self::Number(na), Self::Number(nb)) => Ok(VariableValue::Number(na - nb)),
-            (x, y) => Err(RuntimeError(format!(
-                "Subtraction between {} and {} is not implemented!",
-                x, y
-            ))),
+            (Self::Int(na), Self::Int(nb)) => checked(na.checked_sub(nb), na, "-", nb),
+            (x, y) => match Self::as_floats(&x, &y) {
+                Some((na, nb)) => checked_float(na - nb, &x, "-", &y),
+                None => Err(RuntimeError::from(format!(
+                    "Subtraction between {} and {} is not implemented!",
+                    x, y
+                ))),
+            },
         }
     }
 
     pub fn multiply(a: VariableValue, b: VariableValue) -> Result<VariableValue, RuntimeError> {
         match (a, b) {
-            (Self::Number(na), Self::Number(nb)) => Ok(VariableValue::Number(na * nb)),
-            (x, y) => Err(RuntimeError(format!(
-                "Multiplication between {} and {} is not implemented!",
-                x, y
-            ))),
+            (Self::Int(na), Self::Int(nb)) => checked(na.checked_mul(nb), na, "*", nb),
+            (x, y) => match Self::as_floats(&x, &y) {
+                Some((na, nb)) => checked_float(na * nb, &x, "*", &y),
+                None => Err(RuntimeError::from(format!(
+                    "Multiplication between {} and {} is not implemented!",
+                    x, y
+                ))),
+            },
         }
     }
     pub fn divide(a: VariableValue, b: VariableValue) -> Result<VariableValue, RuntimeError> {
         match (a, b) {
-            (Self::Number(na), Self::Number(nb)) => Ok(VariableValue::Number(na / nb)),
-            (x, y) => Err(RuntimeError(format!(
-                "Division between {} and {} is not implemented!",
-                x, y
-            ))),
+            (x, y) => match Self::as_floats(&x, &y) {
+                Some((_, 0.0)) => Err(divide_by_zero(&x, "divide")),
+                Some((na, nb)) => checked_float(na / nb, &x, "/", &y),
+                None => Err(RuntimeError::from(format!(
+                    "Cannot divide {} by {}",
+                    x.get_type(),
+                    y.get_type()
+                ))),
+            },
+        }
+    }
+    pub fn floor_divide(a: VariableValue, b: VariableValue) -> Result<VariableValue, RuntimeError> {
+        match (a, b) {
+            (Self::Int(na), Self::Int(0)) => Err(divide_by_zero(&Self::Int(na), "floor divide")),
+            (Self::Int(na), Self::Int(nb)) => {
+                checked(floor_div_rem(na, nb).map(|(q, _)| q), na, "//", nb)
+            }
+            (x, y) => match Self::as_floats(&x, &y) {
+                Some((_, 0.0)) => Err(divide_by_zero(&x, "floor divide")),
+                Some((na, nb)) => checked_float((na / nb).floor(), &x, "//", &y),
+                None => Err(RuntimeError::from(format!(
+                    "Cannot floor divide {} by {}",
+                    x.get_type(),
+                    y.get_type()
+                ))),
+            },
         }
     }
     pub fn modulo(a: VariableValue, b: VariableValue) -> Result<VariableValue, RuntimeError> {
         match (a, b) {
-            (Self::Number(na), Self::Number(nb)) => Ok(VariableValue::Number(na % nb)),
-            (x, y) => Err(RuntimeError(format!(
-                "Division between {} and {} is not implemented!",
-                x, y
+            (Self::Int(na), Self::Int(0)) => Err(RuntimeError::from(format!(
+                "Cannot take {} modulo zero",
+                na
             ))),
+            (Self::Int(na), Self::Int(nb)) => {
+                checked(floor_div_rem(na, nb).map(|(_, r)| r), na, "%", nb)
+            }
+            (x, y) => match Self::as_floats(&x, &y) {
+                Some((_, 0.0)) => Err(RuntimeError::from(format!("Cannot take {} modulo zero", x))),
+                Some((na, nb)) => {
+                    let r = na % nb;
+                    Ok(VariableValue::Float(
+                        if r != 0.0 && (r < 0.0) != (nb < 0.0) {
+                            r + nb
+                        } else {
+                            r
+                        },
+                    ))
+                }
+                None => Err(RuntimeError::from(format!(
+                    "Cannot take {} modulo {}",
+                    x.get_type(),
+                    y.get_type()
+                ))),
+            },
         }
     }
-
-    pub fn equals(a: VariableValue, b: VariableValue) -> Result<VariableValue, RuntimeError> {
+    /// The integers from `a` up to `b`, as bounds a loop can walk without building a list.
+    pub fn range_bounds(
+        a: VariableValue,
+        b: VariableValue,
+        inclusive: bool,
+    ) -> Result<std::ops::Range<i128>, RuntimeError> {
+        match (a.thaw(), b.thaw()) {
+            (Self::Int(start), Self::Int(end)) => {
+                let end = if inclusive {
+                    end as i128 + 1
+                } else {
+                    end as i128
+                };
+                Ok(start as i128..end)
+            }
+            (x, y) => Err(RuntimeError::from(format!(
+                "Cannot make a range from {} to {}",
+                x.get_type(),
+                y.get_type()
+            ))),
+        }
+    }
+    /// The list of integers from `a` up to `b`. It is empty if `b` comes before `a`.
+    pub fn range(
+        a: VariableValue,
+        b: VariableValue,
+        inclusive: bool,
+    ) -> Result<VariableValue, RuntimeError> {
+        let bounds = Self::range_bounds(a, b, inclusive)?;
+        if bounds.end - bounds.start > MAX_RANGE_LEN {
+            return Err(RuntimeError::from(format!(
+                "range {}..{} is too long to be a list",
+                bounds.start, bounds.end
+            )));
+        }
+        Ok(VariableValue::List(
+            bounds.map(|n| VariableValue::Int(n as i64)).collect(),
+        ))
+    }
+    /// Integer powers stay integers unless the exponent is negative.
+    pub fn power(a: VariableValue, b: VariableValue) -> Result<VariableValue, RuntimeError> {
         match (a, b) {
-            (Self::Number(na), Self::Number(nb)) => Ok(VariableValue::Boolean(na == nb)),
-            (Self::Boolean(na), Self::Boolean(nb)) => Ok(VariableValue::Boolean(na == nb)),
-            (Self::String(na), Self::String(nb)) => Ok(VariableValue::Boolean(na == nb)),
-            (x, y) => Ok(VariableValue::Boolean(x.get_type() == y.get_type())),
+            (Self::Int(na), Self::Int(nb)) if nb >= 0 => u32::try_from(nb)
+                .ok()
+                .and_then(|e| na.checked_pow(e))
+                .map(VariableValue::Int)
+                .ok_or(RuntimeError::from(format!(
+                    "arithmetic overflow in pow({}, {})",
+                    na, nb
+                ))),
+            (x, y) => match Self::as_floats(&x, &y) {
+                Some((na, nb)) => match na.powf(nb) {
+                    n if n.is_finite() => Ok(VariableValue::Float(n)),
+                    _ => Err(RuntimeError::from(format!(
+                        "pow({}, {}) has no finite result",
+                        x, y
+                    ))),
+                },
+                None => Err(RuntimeError::from(format!(
+                    "Cannot raise {} to the power of {}",
+                    x.get_type(),
+                    y.get_type()
+                ))),
+            },
         }
     }
 
+    pub fn equals(a: VariableValue, b: VariableValue) -> Result<VariableValue, RuntimeError> {
+        Ok(VariableValue::Boolean(Self::is_equal(&a, &b)))
+    }
+
     pub fn not_equals(a: VariableValue, b: VariableValue) -> Result<VariableValue, RuntimeError> {
-        match (a, b) {
-            (Self::Number(na), Self::Number(nb)) => Ok(VariableValue::Boolean(na != nb)),
-            (Self::Boolean(na), Self::Boolean(nb)) => Ok(VariableValue::Boolean(na != nb)),
-            (Self::String(na), Self::String(nb)) => Ok(VariableValue::Boolean(na != nb)),
-            (x, y) => Ok(VariableValue::Boolean(x.get_type() != y.get_type())),
+        Ok(VariableValue::Boolean(!Self::is_equal(&a, &b)))
+    }
+
+    /// Compares lists, objects and bytes by their contents, and frozen values like the values
+    /// inside them. Numbers are equal if they have the same value, whether ints or floats.
+    fn is_equal(a: &VariableValue, b: &VariableValue) -> bool {
+        match (a.thawed(), b.thawed()) {
+            (Self::Int(na), Self::Int(nb)) => na == nb,
+            (Self::Boolean(na), Self::Boolean(nb)) => na == nb,
+            (Self::String(na), Self::String(nb)) => na == nb,
+            (Self::Bytes(na), Self::Bytes(nb)) => na == nb,
+            (Self::List(na), Self::List(nb)) => {
+                na.len() == nb.len() && na.iter().zip(nb).all(|(x, y)| Self::is_equal(x, y))
+            }
+            (Self::Object(na), Self::Object(nb)) => {
+                na.len() == nb.len()
+                    && na
+                        .iter()
+                        .all(|(key, x)| nb.get(key).is_some_and(|y| Self::is_equal(x, y)))
+            }
+            (Self::None, Self::None) => true,
+            (Self::None, _) | (_, Self::None) => false,
+            (x, y) => match Self::as_floats(x, y) {
+                Some((na, nb)) => na == nb,
+                None => x.get_type() == y.get_type(),
+            },
         }
     }
 
-    pub fn less_than(a: VariableValue, b: VariableValue) -> Result<VariableValue, RuntimeError> {
+    /// Orders two numbers or two strings. Floats that are NaN have no order.
+    pub fn compare(
+        a: &VariableValue,
+        b: &VariableValue,
+        op: &str,
+    ) -> Result<Option<Ordering>, RuntimeError> {
         match (a, b) {
-            (Self::Number(na), Self::Number(nb)) => Ok(VariableValue::Boolean(na < nb)),
-            (Self::Boolean(na), Self::Boolean(nb)) => Ok(VariableValue::Boolean(na < nb)),
-            (Self::String(na), Self::String(nb)) => Ok(VariableValue::Boolean(na < nb)),
-            (x, y) => Err(RuntimeError(format!(
-                "Less Than between {} and {} is not implemented!",
-                x, y
-            ))),
+            (Self::Int(na), Self::Int(nb)) => Ok(Some(na.cmp(nb))),
+            (Self::String(na), Self::String(nb)) => Ok(Some(na.cmp(nb))),
+            (x, y) => match Self::as_floats(x, y) {
+                Some((na, nb)) => Ok(na.partial_cmp(&nb)),
+                None => Err(incomparable(x, y, op)),
+            },
         }
     }
 
+    pub fn less_than(a: VariableValue, b: VariableValue) -> Result<VariableValue, RuntimeError> {
+        Self::compare(&a, &b, "<").map(|o| VariableValue::Boolean(o == Some(Ordering::Less)))
+    }
+
     pub fn greater_than(a: VariableValue, b: VariableValue) -> Result<VariableValue, RuntimeError> {
-        match (a, b) {
-            (Self::Number(na), Self::Number(nb)) => Ok(VariableValue::Boolean(na > nb)),
-            (Self::Boolean(na), Self::Boolean(nb)) => Ok(VariableValue::Boolean(na > nb)),
-            (Self::String(na), Self::String(nb)) => Ok(VariableValue::Boolean(na > nb)),
-            (x, y) => Err(RuntimeError(format!(
-                "Greater Than between {} and {} is not implemented!",
-                x, y
-            ))),
-        }
+        Self::compare(&a, &b, ">").map(|o| VariableValue::Boolean(o == Some(Ordering::Greater)))
     }
 
     pub fn less_than_or_equal(
         a: VariableValue,
         b: VariableValue,
     ) -> Result<VariableValue, RuntimeError> {
-        match (a, b) {
-            (Self::Number(na), Self::Number(nb)) => Ok(VariableValue::Boolean(na <= nb)),
-            (Self::Boolean(na), Self::Boolean(nb)) => Ok(VariableValue::Boolean(na <= nb)),
-            (Self::String(na), Self::String(nb)) => Ok(VariableValue::Boolean(na <= nb)),
-            (x, y) => Err(RuntimeError(format!(
-                "Less Than Or Equal between {} and {} is not implemented!",
-                x, y
-            ))),
-        }
+        Self::compare(&a, &b, "<=")
+            .map(|o| VariableValue::Boolean(matches!(o, Some(Ordering::Less | Ordering::Equal))))
     }
 
     pub fn greater_than_or_equal(
         a: VariableValue,
         b: VariableValue,
     ) -> Result<VariableValue, RuntimeError> {
-        match (a, b) {
-            (Self::Number(na), Self::Number(nb)) => Ok(VariableValue::Boolean(na >= nb)),
-            (Self::Boolean(na), Self::Boolean(nb)) => Ok(VariableValue::Boolean(na >= nb)),
-            (Self::String(na), Self::String(nb)) => Ok(VariableValue::Boolean(na >= nb)),
-            (x, y) => Err(RuntimeError(format!(
-                "Greater Than Or Equal between {} and {} is not implemented!",
-                x, y
-            ))),
-        }
+        Self::compare(&a, &b, ">=")
+            .map(|o| VariableValue::Boolean(matches!(o, Some(Ordering::Greater | Ordering::Equal))))
     }
     pub fn and(a: VariableValue, b: VariableValue) -> Result<VariableValue, RuntimeError> {
         match (a, b) {
             (Self::Boolean(na), Self::Boolean(nb)) => Ok(VariableValue::Boolean(na && nb)),
-            (x, y) => Err(RuntimeError(format!(
+            (x, y) => Err(RuntimeError::from(format!(
                 "AND between {} and {} is not implemented!",
                 x, y
             ))),
@@ -289,28 +604,51 @@ impl VariableValue {
     pub fn or(a: VariableValue, b: VariableValue) -> Result<VariableValue, RuntimeError> {
         match (a, b) {
             (Self::Boolean(na), Self::Boolean(nb)) => Ok(VariableValue::Boolean(na || nb)),
-            (x, y) => Err(RuntimeError(format!(
+            (x, y) => Err(RuntimeError::from(format!(
                 "OR between {} and {} is not implemented!",
                 x, y
             ))),
         }
     }
-    pub fn not(a: VariableValue) -> Result<VariableValue, RuntimeError> {
+    pub fn logical_not(a: VariableValue) -> Result<VariableValue, RuntimeError> {
         match a {
             Self::Boolean(na) => Ok(VariableValue::Boolean(!na)),
-            x => Err(RuntimeError(format!("Not for {} is not implemented!", x))),
+            x => Err(RuntimeError::from(format!(
+                "Not for {} is not implemented!",
+                x
+            ))),
+        }
+    }
+    pub fn bit_not(a: VariableValue) -> Result<VariableValue, RuntimeError> {
+        match a {
+            Self::Int(na) => Ok(VariableValue::Int(!na)),
+            x => Err(RuntimeError::from(format!(
+                "Bitwise Not for {} is not implemented!",
+                x
+            ))),
         }
     }
     pub fn negate(a: VariableValue) -> Result<VariableValue, RuntimeError> {
         match a {
-            Self::Number(na) => Ok(VariableValue::Number(-na)),
-            x => Err(RuntimeError(format!("Not for {} is not implemented!", x))),
+            Self::Int(na) => na
+                .checked_neg()
+                .map(VariableValue::Int)
+                .ok_or(RuntimeError::from(format!(
+                    "arithmetic overflow in -({})",
+                    na
+                ))),
+            Self::Float(na) => Ok(VariableValue::Float(-na)),
+            x => Err(RuntimeError::from(format!(
+                "Not for {} is not implemented!",
+                x
+            ))),
         }
     }
     pub fn unary_plus(a: VariableValue) -> Result<VariableValue, RuntimeError> {
         match a {
-            Self::Number(na) => Ok(VariableValue::Number(0 + na)),
-            x => Err(RuntimeError(format!(
+            Self::Int(na) => Ok(VariableValue::Int(na)),
+            Self::Float(na) => Ok(VariableValue::Float(na)),
+            x => Err(RuntimeError::from(format!(
                 "Unary Plus for {} is not implemented!",
                 x
             ))),
@@ -318,16 +656,94 @@ impl VariableValue {
     }
 }
 
+/// Defines the parameters of a function in the current scope. Parameters without an argument
+/// are set to their default, which can refer to the parameters before them.
+fn bind_params(
+    ctx: &mut Context,
+    args: &[Parameter],
+    params: &[VariableValue],
+) -> Result<(), Command> {
+    for (i, arg) in args.iter().enumerate() {
+        if arg.rest {
+            let rest = params.get(i..).unwrap_or_default().to_vec();
+            define_var_by_val(ctx, &arg.name, VariableValue::List(rest))?;
+            continue;
+        }
+        let val = match (params.get(i), &arg.default) {
+            (Some(val), _) => val.clone(),
+            (None, Some(default)) => eval_expr(ctx, default)?,
+            (None, None) => VariableValue::Unit,
+        };
+        define_var_by_val(ctx, &arg.name, val)?;
+    }
+    Ok(())
+}
+
+/// Only numbers and strings are ordered. Booleans and other values can only be compared for
+/// equality.
+fn incomparable(a: &VariableValue, b: &VariableValue, op: &str) -> RuntimeError {
+    RuntimeError::from(format!(
+        "Cannot compare {} and {} with '{}'",
+        a.get_type(),
+        b.get_type(),
+        op
+    ))
+}
+
+/// The result of integer arithmetic, or an error if it overflowed.
+fn checked(result: Option<i64>, a: i64, op: &str, b: i64) -> Result<VariableValue, RuntimeError> {
+    result
+        .map(VariableValue::Int)
+        .ok_or(RuntimeError::from(format!(
+            "arithmetic overflow in {} {} {}",
+            a, op, b
+        )))
+}
+
+/// The result of float arithmetic, or an error if it isn't a finite number.
+fn checked_float(
+    result: f64,
+    a: &VariableValue,
+    op: &str,
+    b: &VariableValue,
+) -> Result<VariableValue, RuntimeError> {
+    if result.is_finite() {
+        Ok(VariableValue::Float(result))
+    } else {
+        Err(RuntimeError::from(format!(
+            "arithmetic overflow in {} {} {}",
+            a, op, b
+        )))
+    }
+}
+
+/// The quotient rounded towards negative infinity and the remainder that goes with it, which
+/// has the sign of `b`, so that `a == q * b + r`. `None` if `b` is zero or the quotient overflows.
+fn floor_div_rem(a: i64, b: i64) -> Option<(i64, i64)> {
+    let (q, r) = (a.checked_div(b)?, a.checked_rem(b)?);
+    if r != 0 && (r < 0) != (b < 0) {
+        Some((q.checked_sub(1)?, r + b))
+    } else {
+        Some((q, r))
+    }
+}
+
+fn divide_by_zero(a: &VariableValue, verb: &str) -> RuntimeError {
+    RuntimeError::from(format!("Cannot {} {} by zero", verb, a))
+}
+
 pub fn evaluate_binary_op(
     a: VariableValue,
     b: VariableValue,
     op: Operator,
 ) -> Result<VariableValue, RuntimeError> {
+    let (a, b) = (a.thaw(), b.thaw());
     match op {
         Operator::Add => VariableValue::add(a, b),
         Operator::Subtract => VariableValue::subtract(a, b),
         Operator::Multiply => VariableValue::multiply(a, b),
         Operator::Divide => VariableValue::divide(a, b),
+        Operator::FloorDivide => VariableValue::floor_divide(a, b),
         Operator::Equal => VariableValue::equals(a, b),
         Operator::NotEqual => VariableValue::not_equals(a, b),
         Operator::LessThan => VariableValue::less_than(a, b),
@@ -337,16 +753,23 @@ pub fn evaluate_binary_op(
         Operator::And => VariableValue::and(a, b),
         Operator::Or => VariableValue::or(a, b),
         Operator::Modulo => VariableValue::modulo(a, b),
-        _ => Err(RuntimeError(format!("{:?} is not a binary operator!", op))),
+        Operator::Range => VariableValue::range(a, b, false),
+        Operator::RangeInclusive => VariableValue::range(a, b, true),
+        _ => Err(RuntimeError::from(format!(
+            "{:?} is not a binary operator!",
+            op
+        ))),
     }
 }
 
 pub fn evaluate_unary_op(a: VariableValue, op: Operator) -> Result<VariableValue, Command> {
+    let a = a.thaw();
     match op {
-        Operator::Not => VariableValue::not(a).map_err(|v| Command::Error(v)),
-        Operator::Negate => VariableValue::negate(a).map_err(|v| Command::Error(v)),
-        Operator::UnaryPlus => VariableValue::unary_plus(a).map_err(|v| Command::Error(v)),
-        _ => Err(Command::Error(RuntimeError(format!(
+        Operator::Not => VariableValue::logical_not(a).map_err(Command::Error),
+        Operator::BitNot => VariableValue::bit_not(a).map_err(Command::Error),
+        Operator::Negate => VariableValue::negate(a).map_err(Command::Error),
+        Operator::UnaryPlus => VariableValue::unary_plus(a).map_err(Command::Error),
+        _ => Err(Command::Error(RuntimeError::from(format!(
             "{:?} is not a unary operator!",
             op
         )))),
@@ -0,0 +1,53 @@
+mod common;
+
+use common::*;
+
+#[test]
+fn bytes_are_indexed_as_numbers() {
+    assert_eq!(
+        output("let b = bytes_from_string(\"hé\"); print(b, b[0], b[2], b.len());"),
+        "bytes[104, 195, 169] 104 169 3\n"
+    );
+    assert_eq!(
+        output("let b = bytes_from_string(\"ab\"); b[1] = 255; print(b, bytes_to_string(bytes_from_string(\"ok\")));"),
+        "bytes[97, 255] \"ok\"\n"
+    );
+}
+
+#[test]
+fn bytes_reject_values_outside_0_to_255() {
+    let assign = |n: &str| error(&format!("let b = bytes_from_string(\"a\"); b[0] = {};", n));
+    assert_eq!(
+        assign("256"),
+        "A byte must be an integer from 0 to 255, got 256"
+    );
+    assert_eq!(
+        assign("0 - 1"),
+        "A byte must be an integer from 0 to 255, got -1"
+    );
+}
+
+#[test]
+fn bytes_are_encoded_and_decoded() {
+    assert_eq!(
+        output("let b = bytes_from_string(\"hé\"); print(hex_encode(b), base64_encode(b));"),
+        "\"68c3a9\" \"aMOp\"\n"
+    );
+    assert_eq!(
+        output("print(hex_decode(\"68c3a9\"), base64_decode(\"aGk=\"));"),
+        "bytes[104, 195, 169] bytes[104, 105]\n"
+    );
+}
+
+#[test]
+fn bytes_are_read_from_files() {
+    let path = std::env::temp_dir().join(format!("slang-bytes-{}", std::process::id()));
+    let path = path.to_string_lossy().replace('\\', "/");
+    let program = format!(
+        "write_file(\"{0}\", \"hi\"); print(read_bytes(\"{0}\"));",
+        path
+    );
+    let printed = output(&program);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(printed, "bytes[104, 105]\n");
+}
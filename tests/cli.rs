@@ -0,0 +1,61 @@
+use std::process::Command;
+
+/// Runs the binary on a program written to a temporary file and returns its stdout and stderr.
+fn run_file(name: &str, program: &str, args: &[&str]) -> (String, String) {
+    let path =
+        std::env::temp_dir().join(format!("slang-cli-{}-{}.slang", name, std::process::id()));
+    std::fs::write(&path, program).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_slang"))
+        .args(args)
+        .arg(&path)
+        .output()
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    (
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+#[test]
+fn print_result_prints_the_value_of_the_program() {
+    let program = "print(1);\n[1, 2, 3].join(\"+\")";
+    assert_eq!(run_file("silent", program, &[]).0, "1\n");
+    assert_eq!(
+        run_file("result", program, &["--print-result"]).0,
+        "1\n\"1+2+3\"\n"
+    );
+    assert_eq!(run_file("unit", "let x = 1;", &["--print-result"]).0, "");
+}
+
+#[test]
+fn every_syntax_error_of_a_file_is_reported() {
+    let (stdout, stderr) = run_file("syntax", "let a = 1 +;\nprint(1);\nlet b = (2 *);\n", &[]);
+    assert_eq!(stdout, "");
+    assert!(
+        stderr.contains("missing operand after '+' at line 1, column 11"),
+        "{}",
+        stderr
+    );
+    assert!(
+        stderr.contains("missing operand after '*' at line 3, column 12"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn eprint_writes_to_stderr_and_print_to_stdout() {
+    let (stdout, stderr) = run_file(
+        "streams",
+        "print(\"data\"); eprint(\"warning:\", 42); print(1);",
+        &[],
+    );
+    assert_eq!(stdout, "\"data\"\n1\n");
+    assert_eq!(stderr, "\"warning:\" 42\n");
+}
@@ -0,0 +1,60 @@
+//! Runs programs the way a host embedding slang would, with the output captured.
+#![allow(dead_code)]
+
+use slang::*;
+
+/// A context whose `print` and `eprint` output end up in the returned buffers.
+pub fn capturing_context() -> (Context, OutputBuffer, OutputBuffer) {
+    let output = OutputBuffer::default();
+    let error_output = OutputBuffer::default();
+    let ctx = Context::new(".")
+        .with_output(Box::new(output.clone()))
+        .with_error_output(Box::new(error_output.clone()));
+    (ctx, output, error_output)
+}
+
+/// Runs a program that must succeed and returns what it printed.
+pub fn output(program: &str) -> String {
+    let (mut ctx, output, _) = capturing_context();
+    if let Err(e) = execute_program(&mut ctx, program) {
+        panic!("program failed: {}", e);
+    }
+    output.contents()
+}
+
+/// Runs a program that must succeed and returns the value it evaluates to, as `print` shows it.
+pub fn value(program: &str) -> String {
+    match run(program) {
+        Ok(v) => v.to_string(),
+        Err(e) => panic!("program failed: {}", e),
+    }
+}
+
+/// Runs a program that must fail and returns the message of its error, without the position.
+pub fn error(program: &str) -> String {
+    let (mut ctx, _, _) = capturing_context();
+    match execute_program(&mut ctx, program) {
+        Ok(v) => panic!("expected an error, but the program returned {}", v),
+        Err(e) => message(&e),
+    }
+}
+
+pub fn message(e: &Error) -> String {
+    match e {
+        Error::C(ClientError(m)) | Error::S(SyntaxError(m, _)) | Error::R(RuntimeError(m, _)) => {
+            m.clone()
+        }
+    }
+}
+
+/// Runs a program that must fail and returns the line and column its error points at.
+pub fn position(program: &str) -> (usize, usize) {
+    let (mut ctx, _, _) = capturing_context();
+    match execute_program(&mut ctx, program) {
+        Ok(v) => panic!("expected an error, but the program returned {}", v),
+        Err(Error::S(SyntaxError(_, Some(l)))) | Err(Error::R(RuntimeError(_, Some(l)))) => {
+            (l.span.line, l.span.column)
+        }
+        Err(e) => panic!("the error has no position: {}", e),
+    }
+}
@@ -0,0 +1,123 @@
+mod common;
+
+use common::*;
+
+#[test]
+fn for_loop_destructures_object_fields() {
+    let program = r#"
+        for [key, val] in {a: 1, b: 2, c: 3} {
+            print(key, val);
+        };
+    "#;
+    assert_eq!(output(program), "\"a\" 1\n\"b\" 2\n\"c\" 3\n");
+}
+
+#[test]
+fn for_loop_destructures_lists() {
+    let program = r#"
+        for [x, y] in [[1, 2], [3, 4]] {
+            print(x + y);
+        };
+    "#;
+    assert_eq!(output(program), "3\n7\n");
+    assert_eq!(
+        error("for [x, y] in [[1, 2, 3]] { print(x); };"),
+        "cannot destructure a list of length 3 into 2 variables"
+    );
+}
+
+#[test]
+fn for_loop_visits_object_fields_as_pairs_in_definition_order() {
+    let program = r#"
+        let total = 0;
+        let seen = [];
+        for entry in {b: 20, c: 300, a: 1} {
+            total = total + entry[1];
+            seen = seen + [entry];
+        };
+        for entry in {} { print(entry); };
+        print(total, seen);
+    "#;
+    assert_eq!(
+        output(program),
+        "321 [[\"b\", 20], [\"c\", 300], [\"a\", 1]]\n"
+    );
+}
+
+#[test]
+fn for_loop_visits_the_characters_of_a_string() {
+    let program = r#"
+        let vowels = 0;
+        let chars = [];
+        for c in "Hélló, wörld 😀" {
+            if "aeiouéóö".contains(c) { vowels = vowels + 1; };
+            chars = chars + [c];
+        };
+        for c in "" { print(c); };
+        print(vowels, chars.len(), chars[13]);
+    "#;
+    assert_eq!(output(program), "3 14 \"😀\"\n");
+}
+
+#[test]
+fn for_loop_walks_a_range_without_building_it() {
+    let program = r#"
+        let sum = 0;
+        for i in 1..=4 { sum = sum + i; };
+        for i in 3..0 { print("never"); };
+        let found = for i in 0..9000000000000000000 { if i * i > 50 { break i; }; };
+        let last = for i in 9223372036854775806..=9223372036854775807 { if i % 2 == 1 { break i; }; };
+        print(sum, found, last);
+    "#;
+    assert_eq!(output(program), "10 8 9223372036854775807\n");
+    assert_eq!(
+        error("for i in 0..\"3\" { };"),
+        "Cannot make a range from Int to String"
+    );
+}
+
+#[test]
+fn match_picks_the_first_arm_equal_to_the_scrutinee() {
+    let program = r#"
+        let name = |n| match n {
+            1: "one",
+            2: "two",
+            "2": "string two",
+            _: "many",
+        };
+        print(name(1), name(2), name("2"), name(7));
+    "#;
+    assert_eq!(output(program), "\"one\" \"two\" \"string two\" \"many\"\n");
+}
+
+#[test]
+fn match_evaluates_the_scrutinee_once() {
+    let program = r#"
+        let calls = 0;
+        let next = || { calls = calls + 1; calls };
+        let result = match next() { 2: "second", 1: "first", _: "other" };
+        print(result, calls);
+    "#;
+    assert_eq!(output(program), "\"first\" 1\n");
+}
+
+#[test]
+fn match_without_a_matching_arm_is_an_error() {
+    assert_eq!(error("match 3 { 1: 1, 2: 2 };"), "no match arm matched 3");
+}
+
+#[test]
+fn let_in_binds_only_inside_its_expression() {
+    assert_eq!(
+        output("let r = 10; let area = let r = 2 in r * r * 3; print(area, r);"),
+        "12 10\n"
+    );
+    assert_eq!(
+        value("[let a = 1 in a + 1, let b = 2 in let c = 3 in b * c]"),
+        "[2, 6]"
+    );
+    assert_eq!(
+        error("let x = let y = 1 in y; print(y);"),
+        "variable is not defined"
+    );
+}
@@ -0,0 +1,109 @@
+mod common;
+
+use common::*;
+use slang::*;
+
+#[test]
+fn run_returns_the_value_of_the_program() {
+    assert!(matches!(run("1 + 2"), Ok(VariableValue::Int(3))));
+    assert_eq!(value("let x = [1, 2]; x + [3]"), "[1, 2, 3]");
+    assert!(matches!(run("let x = 1;"), Ok(VariableValue::Unit)));
+}
+
+#[test]
+fn run_reports_syntax_and_runtime_errors() {
+    assert!(matches!(run("let = ;"), Err(Error::S(_))));
+    match run("1 / 0") {
+        Err(e @ Error::R(_)) => assert_eq!(message(&e), "Cannot divide 1 by zero"),
+        other => panic!(
+            "expected a runtime error, got {:?}",
+            other.map(|v| v.to_string())
+        ),
+    }
+}
+
+#[test]
+fn scripts_can_call_registered_native_functions() {
+    let (mut ctx, output, _) = capturing_context();
+    ctx.register_fn(
+        "double",
+        Box::new(|args| match args.as_slice() {
+            [VariableValue::Int(n)] => Ok(VariableValue::Int(n * 2)),
+            _ => Err(RuntimeError::from("double takes one integer")),
+        }),
+    );
+    let result = execute_program(&mut ctx, "print(double(21)); double(double(2))");
+    assert!(matches!(result, Ok(VariableValue::Int(8))));
+    assert_eq!(output.contents(), "42\n");
+
+    let error = execute_program(&mut ctx, "double(\"a\")").unwrap_err();
+    assert_eq!(message(&error), "double takes one integer");
+}
+
+#[test]
+fn pipe_lines_maps_each_input_line() {
+    let (ctx, output, _) = capturing_context();
+    let mut ctx = ctx.with_input(Box::new("one\ntwo\n\nthree".as_bytes()));
+    let program = "let n = 0;
+        pipe_lines(|line| { n = n + 1; if line != \"\" { line + \"!\" } else { print(\"-\"); } });
+        n";
+    assert!(matches!(
+        execute_program(&mut ctx, program),
+        Ok(VariableValue::Int(4))
+    ));
+    assert_eq!(output.contents(), "\"one!\"\n\"two!\"\n\"-\"\n\"three!\"\n");
+}
+
+#[test]
+fn fuel_stops_endless_loops() {
+    let (ctx, _, _) = capturing_context();
+    let mut ctx = ctx.with_fuel(1000);
+    let error = execute_program(&mut ctx, "let n = 0; while true { n = n + 1; };").unwrap_err();
+    assert_eq!(message(&error), "execution budget exhausted");
+    assert_eq!(ctx.remaining_fuel(), Some(0));
+
+    let (ctx, _, _) = capturing_context();
+    let mut ctx = ctx.with_fuel(1000);
+    assert_eq!(execute_program(&mut ctx, "1 + 2").unwrap().to_string(), "3");
+    assert!(ctx
+        .remaining_fuel()
+        .is_some_and(|fuel| fuel > 0 && fuel < 1000));
+    assert_eq!(capturing_context().0.remaining_fuel(), None);
+}
+
+#[test]
+fn the_truncate_index_policy_rounds_float_indices_towards_zero() {
+    let (ctx, _, _) = capturing_context();
+    let mut ctx = ctx.with_index_policy(IndexPolicy::Truncate);
+    let program = "let l = [10, 20, 30]; l[1.9] = 21; [l[0.2], l[1.9], l[-1.5], \"abc\"[2.7], l]";
+    assert_eq!(
+        execute_program(&mut ctx, program).unwrap().to_string(),
+        "[10, 21, 30, \"c\", [10, 21, 30]]"
+    );
+    assert_eq!(
+        error("let l = [10, 20, 30]; l[1.9] = 21;"),
+        "index must be an integer, got 1.9"
+    );
+}
+
+#[test]
+fn truthy_conditions_accept_any_value() {
+    let program = "let results = [];
+        for v in [false, 0, 0.0, none, \"\", [], {}, bytes_from_string(\"\"), true, 1, -0.5, \"0\", [0], {a: none}] {
+            results = results + [if v { 1 } else { 0 }];
+        };
+        let n = 3; let count = 0;
+        while n { n = n - 1; count = count + 1; };
+        [results, count]";
+    let (ctx, _, _) = capturing_context();
+    let mut ctx = ctx.with_truthy_conditions(true);
+    assert_eq!(
+        execute_program(&mut ctx, program).unwrap().to_string(),
+        "[[0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1], 3]"
+    );
+    assert_eq!(
+        error("if 1 { 2 } else { 3 };"),
+        "condition is not a boolean"
+    );
+    assert_eq!(error("while [] {};"), "condition is not a boolean");
+}
@@ -0,0 +1,94 @@
+mod common;
+
+use common::*;
+use slang::*;
+
+#[test]
+fn syntax_errors_point_at_the_offending_token() {
+    assert_eq!(position("let x = 1;\nlet y = x +;"), (2, 11));
+    assert_eq!(position("let x = 1;\n\n  let = 2;"), (3, 3));
+    assert_eq!(error("let x = \"open;"), "unterminated string literal");
+    assert_eq!(position("print(1);\n  let x = \"open;"), (2, 11));
+}
+
+#[test]
+fn unterminated_strings_point_at_their_opening_quote() {
+    let program = "let a = 1;\nlet s = \"hello\nworld;\nprint(s);\n";
+    assert_eq!(error(program), "unterminated string literal");
+    assert_eq!(position(program), (2, 9));
+    assert_eq!(position("print(\"a\");\n\""), (2, 1));
+    assert_eq!(position("'a"), (1, 1));
+}
+
+#[test]
+fn runtime_errors_point_at_the_failing_expression() {
+    assert_eq!(position("let x = 1;\nlet y = x / 0;"), (2, 11));
+    assert_eq!(position("let f = |x| x;\n  f(1, 2);"), (2, 3));
+    assert_eq!(position("print(1);\nlet y = missing;"), (2, 9));
+}
+
+#[test]
+fn assigning_to_a_call_result_is_a_specific_error() {
+    let program = "let f = || [1];\n  f() = 1;";
+    assert_eq!(
+        error(program),
+        "cannot assign to the result of a function call"
+    );
+    assert_eq!(position(program), (2, 3));
+    assert_eq!(
+        error("let o = {f: || 1}; o.f() = 2;"),
+        "cannot assign to the result of a function call"
+    );
+    assert_eq!(error("1 = 2;"), "can only assign to a reference");
+}
+
+#[test]
+fn match_without_a_matching_arm_points_at_the_match() {
+    let program = "let x = 3;\nlet y = match x {\n  1: \"one\",\n};";
+    assert_eq!(error(program), "no match arm matched 3");
+    assert_eq!(position(program), (2, 9));
+}
+
+#[test]
+fn float_index_errors_point_at_the_index() {
+    assert_eq!(position("let xs = [1, 2];\nprint(xs[0.5]);"), (2, 9));
+    assert_eq!(position("let xs = [1, 2];\n  xs[0.5] = 1;"), (2, 5));
+}
+
+#[test]
+fn errors_render_the_source_line_with_a_caret() {
+    let rendered = |program: &str| {
+        let (mut ctx, _, _) = capturing_context();
+        execute_program(&mut ctx, program).unwrap_err().to_string()
+    };
+    assert_eq!(
+        rendered("let x = 1;\nlet y = x +;"),
+        "missing operand after '+' at line 2, column 11\n 2 | let y = x +;\n   |           ^"
+    );
+    assert_eq!(
+        rendered("let x = 0;\n\tprint(1 / x);"),
+        "Cannot divide 1 by zero at line 2, column 10\n 2 | \tprint(1 / x);\n   | \t        ^"
+    );
+}
+
+#[test]
+fn parse_program_reports_every_statement_that_fails_to_parse() {
+    let errors = parse_program("let a = 1 +;\nlet ok = 2;\nlet b = (2 *);\nprint(a);").unwrap_err();
+    let found: Vec<(String, Option<(usize, usize)>)> = errors
+        .iter()
+        .map(|SyntaxError(message, location)| {
+            (
+                message.clone(),
+                location.as_ref().map(|l| (l.span.line, l.span.column)),
+            )
+        })
+        .collect();
+    assert_eq!(
+        found,
+        [
+            ("missing operand after '+'".to_string(), Some((1, 11))),
+            ("missing operand after '*'".to_string(), Some((3, 12))),
+        ]
+    );
+    assert!(parse_program("let a = 1;\nprint(a);").is_ok_and(|statements| statements.len() == 2));
+}
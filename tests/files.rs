@@ -0,0 +1,48 @@
+mod common;
+
+use common::*;
+use slang::*;
+
+/// A path in the temporary directory that no other test uses.
+fn temp_path(name: &str) -> String {
+    let path = std::env::temp_dir().join(format!("slang-files-{}-{}", name, std::process::id()));
+    path.to_str().unwrap().to_string()
+}
+
+#[test]
+fn files_round_trip_through_write_file_and_read_file() {
+    let path = temp_path("round-trip.txt");
+    let program = format!(
+        "write_file({:?}, \"héllo\\nworld\"); [read_file({:?}), read_bytes({:?}).len()]",
+        path, path, path
+    );
+    assert_eq!(value(&program), "[\"héllo\nworld\", 12]");
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "héllo\nworld");
+    std::fs::remove_file(&path).unwrap();
+    let message = error(&format!("read_file({:?});", path));
+    assert!(
+        message.starts_with(&format!("Cannot read {}", path)),
+        "{}",
+        message
+    );
+    assert_eq!(
+        error("write_file(\"x.txt\", 1);"),
+        "write_file expects a path and a string"
+    );
+}
+
+#[test]
+fn filesystem_access_can_be_denied() {
+    let path = temp_path("denied.txt");
+    for program in [
+        format!("write_file({:?}, \"a\");", path),
+        format!("read_file({:?});", path),
+        format!("read_bytes({:?});", path),
+    ] {
+        let (ctx, _, _) = capturing_context();
+        let mut ctx = ctx.with_filesystem_access(false);
+        let error = execute_program(&mut ctx, &program).unwrap_err();
+        assert!(message(&error).ends_with("needs filesystem access, which is disabled"));
+    }
+    assert!(!std::path::Path::new(&path).exists());
+}
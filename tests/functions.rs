@@ -0,0 +1,164 @@
+mod common;
+
+use common::*;
+use slang::*;
+
+const FLAKY: &str = "let calls = 0;
+    let flaky = || { calls = calls + 1; if calls < 3 { 1 / 0; }; calls * 10 };";
+
+#[test]
+fn retry_returns_the_first_success() {
+    assert_eq!(
+        output(&format!("{} print(retry(flaky, 3), calls);", FLAKY)),
+        "30 3\n"
+    );
+}
+
+#[test]
+fn retry_raises_the_last_error() {
+    assert_eq!(
+        error(&format!("{} retry(flaky, 2);", FLAKY)),
+        "Cannot divide 1 by zero"
+    );
+    assert_eq!(
+        output(&format!(
+            "{} let r = retry(flaky, 5); print(r, calls);",
+            FLAKY
+        )),
+        "30 3\n"
+    );
+}
+
+#[test]
+fn assert_passes_on_true_and_fails_otherwise() {
+    assert_eq!(
+        output("assert(true); assert(1 + 1 == 2, \"math\"); print(1);"),
+        "1\n"
+    );
+    assert_eq!(error("assert(false);"), "assertion failed");
+    assert_eq!(
+        error("assert(1 > 2, \"one is not more\");"),
+        "assertion failed: one is not more"
+    );
+    assert_eq!(
+        error("assert(2);"),
+        "assert expects a boolean condition, got 2"
+    );
+}
+
+#[test]
+fn assert_throws_returns_the_error_message() {
+    assert_eq!(
+        value("let divide = |a, b| a / b; assert_throws(|| divide(1, 0))"),
+        "\"Cannot divide 1 by zero\""
+    );
+    assert_eq!(
+        error("assert_throws(|| 1);"),
+        "expected an error, but the function returned 1"
+    );
+}
+
+#[test]
+fn endless_recursion_fails_at_the_default_depth_on_a_test_thread() {
+    let program = "let f = |n| { let pair = [n, {n: n}]; if n > 0 { f(n - 1) + 1 } else { 0 } };";
+    assert_eq!(value(&format!("{} f(999)", program)), "999");
+    assert_eq!(
+        error(&format!("{} f(1000);", program)),
+        "maximum recursion depth exceeded"
+    );
+    assert_eq!(
+        error("let f = |n| f(n + 1); f(0);"),
+        "maximum recursion depth exceeded"
+    );
+}
+
+#[test]
+fn a_raised_call_depth_limit_does_not_overflow_the_stack() {
+    let (ctx, _, _) = capturing_context();
+    let mut ctx = ctx.with_max_call_depth(3000);
+    let result = execute_program(
+        &mut ctx,
+        "let f = |n| if n > 0 { f(n - 1) + 1 } else { 0 }; f(2999)",
+    );
+    assert_eq!(result.unwrap().to_string(), "2999");
+}
+
+#[test]
+fn calls_must_pass_as_many_arguments_as_there_are_parameters() {
+    let program = "let add = |a, b| { return a + b; }; let one = |x| x; let nothing = || 1; let o = {f: |a, b| a};";
+    let call = |expr: &str| error(&format!("{} {};", program, expr));
+    assert_eq!(
+        value(&format!(
+            "{} [add(1, 2), one(3), nothing(), o.f(4, 5)]",
+            program
+        )),
+        "[3, 3, 1, 4]"
+    );
+    assert_eq!(call("add(1)"), "function expected 2 arguments, got 1");
+    assert_eq!(call("add(1, 2, 3)"), "function expected 2 arguments, got 3");
+    assert_eq!(call("one()"), "function expected 1 argument, got 0");
+    assert_eq!(call("nothing(1)"), "function expected 0 arguments, got 1");
+    assert_eq!(call("o.f(1)"), "function expected 2 arguments, got 1");
+}
+
+#[test]
+fn trailing_parameters_can_have_defaults() {
+    assert_eq!(
+        value("let f = |x, y: 10| x + y; let g = |x, y: x * 2, z: y + 1| [x, y, z]; [f(1), f(1, 2), g(3), g(3, 1)]"),
+        "[11, 3, [3, 6, 7], [3, 1, 2]]"
+    );
+    assert_eq!(
+        error("let f = |x, y: 10| x; f();"),
+        "function expected 1 to 2 arguments, got 0"
+    );
+    assert_eq!(
+        error("let f = |a: 1, b| a;"),
+        "parameter 'b' without a default follows one with a default"
+    );
+}
+
+#[test]
+fn a_rest_parameter_collects_the_extra_arguments() {
+    assert_eq!(
+        value("let f = |a, b, ...rest| [a, b, rest]; [f(1, 2), f(1, 2, 3), f(1, 2, 3, 4, 5)]"),
+        "[[1, 2, []], [1, 2, [3]], [1, 2, [3, 4, 5]]]"
+    );
+    assert_eq!(value("let all = |...xs| xs; [all(), all(1)]"), "[[], [1]]");
+    assert_eq!(
+        error("let f = |a, ...rest| rest; f();"),
+        "function expected at least 1 argument, got 0"
+    );
+    assert_eq!(
+        error("|...r, a| a;"),
+        "parameter 'a' follows the rest parameter"
+    );
+}
+
+#[test]
+fn calls_indices_and_fields_chain_left_to_right() {
+    assert_eq!(
+        output(
+            "let a = {b: || [{c: || 42, d: [7, 8]}]};
+            print(a.b()[0].c(), a.b()[0].d[1], a.b()[0][\"c\"](), \"  hi \".trim().to_upper());"
+        ),
+        "42 8 42 \"HI\"\n"
+    );
+    assert_eq!(
+        error("let a = {b: || [1]}; a.b()[0].c();"),
+        "Variable '1' is not an object"
+    );
+}
+
+#[test]
+fn builtins_can_be_called_as_methods_of_their_first_argument() {
+    assert_eq!(
+        output(
+            "print([1, 2, 3].len(), \"a,b\".split(\",\"), split(\"a,b\", \",\"), [3, 1, 2].sum());"
+        ),
+        "3 [\"a\", \"b\"] [\"a\", \"b\"] 6\n"
+    );
+    assert_eq!(
+        value("let o = {len: || 5, split: |sep| sep}; [o.len(), o.split(\",\")]"),
+        "[5, \",\"]"
+    );
+}
@@ -0,0 +1,42 @@
+mod common;
+
+use common::*;
+use slang::*;
+
+#[test]
+fn print_takes_a_separator_and_an_end() {
+    assert_eq!(output("print(1, 2, 3);"), "1 2 3\n");
+    assert_eq!(
+        output("print(1, 2, 3, sep = \", \", end = \"\"); print(4, end = \"!\");"),
+        "1, 2, 34!"
+    );
+    assert_eq!(error("print(1, sep = 2);"), "sep must be a string, got 2");
+    assert_eq!(
+        error("print(1, start = \"\");"),
+        "print has no argument named 'start'"
+    );
+}
+
+#[test]
+fn eprint_writes_to_the_error_output() {
+    let (mut ctx, output, error_output) = capturing_context();
+    execute_program(
+        &mut ctx,
+        "eprint(\"a\", \"b\", sep = \"-\", end = \";\"); print(1);",
+    )
+    .unwrap();
+    assert_eq!(output.contents(), "1\n");
+    assert_eq!(error_output.contents(), "\"a\"-\"b\";");
+}
+
+#[test]
+fn input_reads_lines_until_the_end() {
+    let (ctx, output, _) = capturing_context();
+    let mut ctx = ctx.with_input(Box::new("ada\r\nbob\n".as_bytes()));
+    let program = "let a = input(\"Name: \"); let b = input(); [a, b, input(), input() == none]";
+    assert_eq!(
+        execute_program(&mut ctx, program).unwrap().to_string(),
+        "[\"ada\", \"bob\", none, true]"
+    );
+    assert_eq!(output.contents(), "Name: ");
+}
@@ -0,0 +1,52 @@
+mod common;
+
+use common::*;
+use slang::*;
+
+#[test]
+fn values_serialize_to_json() {
+    let json = |program: &str| run(program).unwrap().to_json();
+    assert_eq!(
+        json("[1, 2.5, true, \"a\\\"b\", none]"),
+        r#"[1,2.5,true,"a\"b",null]"#
+    );
+    assert_eq!(
+        json("{a: [1, {b: \"x\"}], c: {d: []}}"),
+        r#"{"a":[1,{"b":"x"}],"c":{"d":[]}}"#
+    );
+    assert_eq!(
+        json("[|x| x, bytes_from_string(\"hi\")]"),
+        "[null,[104,105]]"
+    );
+}
+
+#[test]
+fn json_parses_into_values() {
+    assert_eq!(
+        output(
+            r#"let v = parse_json("{\"a\": [1, 2.5, {\"b\": null}], \"c\": \"\\u00e9\"}");
+            print(v.a[0] + 1, v.a[1], v.a[2].b, v.c);"#
+        ),
+        "2 2.5 none \"é\"\n"
+    );
+    assert!(matches!(
+        from_json(" [true, [], {}] "),
+        Ok(VariableValue::List(list)) if list.len() == 3
+    ));
+}
+
+#[test]
+fn invalid_json_reports_the_position() {
+    assert_eq!(
+        error("parse_json(\"[1, 2,, 3]\");"),
+        "invalid JSON: unexpected ',' at line 1, column 7"
+    );
+    assert_eq!(
+        from_json("{\"a\": 1\n\"b\": 2}").unwrap_err().0,
+        "invalid JSON: expected ',' or '}' at line 2, column 1"
+    );
+    assert_eq!(
+        from_json("[01]").unwrap_err().0,
+        "invalid JSON: numbers cannot have leading zeros at line 1, column 2"
+    );
+}
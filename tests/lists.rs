@@ -0,0 +1,231 @@
+mod common;
+
+use common::*;
+
+#[test]
+fn downto_counts_down_including_both_ends() {
+    assert_eq!(value("downto(5, 1)"), "[5, 4, 3, 2, 1]");
+    assert_eq!(value("downto(2, 2)"), "[2]");
+    assert_eq!(error("downto(1, 5)"), "downto expects 1 to be at least 5");
+}
+
+#[test]
+fn downto_to_the_smallest_integer_is_an_error() {
+    assert_eq!(
+        error("downto(0, 0 - 9223372036854775807 - 1)"),
+        "downto cannot count down to the smallest integer -9223372036854775808"
+    );
+}
+
+#[test]
+fn range_infers_the_direction_of_two_bounds() {
+    assert_eq!(value("range(5, 1)"), "[5, 4, 3, 2]");
+    assert_eq!(value("range(5, 1, -2)"), "[5, 3]");
+    assert_eq!(
+        error("range(1, 5, -1)"),
+        "range step -1 does not match the direction from 1 to 5"
+    );
+}
+
+#[test]
+fn range_takes_a_stop_bounds_or_a_step() {
+    assert_eq!(value("range(3)"), "[0, 1, 2]");
+    assert_eq!(value("range(0)"), "[]");
+    assert_eq!(value("range(2, 5)"), "[2, 3, 4]");
+    assert_eq!(value("range(3, 3)"), "[]");
+    assert_eq!(value("range(0, 10, 4)"), "[0, 4, 8]");
+    assert_eq!(value("range(5, 0, -2)"), "[5, 3, 1]");
+    assert_eq!(output("for i in range(3) { print(i); };"), "0\n1\n2\n");
+}
+
+#[test]
+fn range_with_a_zero_step_is_an_error() {
+    assert_eq!(error("range(1, 5, 0)"), "range step cannot be zero");
+}
+
+#[test]
+fn contains_finds_elements_substrings_and_keys() {
+    assert_eq!(
+        value("[[1, 2].contains(2.0), contains([1, 2], 3), contains([[1], {a: 2}], {a: 2}), [[1]].contains([2])]"),
+        "[true, false, true, false]"
+    );
+    assert_eq!(
+        value("[\"hello\".contains(\"ell\"), contains(\"hello\", \"xyz\"), contains({a: 1}, \"a\"), {a: 1}.contains(\"b\")]"),
+        "[true, false, true, false]"
+    );
+    assert_eq!(
+        error("contains(1, 1);"),
+        "contains expects a list, a string or an object and an item"
+    );
+}
+
+#[test]
+fn compact_drops_units_and_nones() {
+    assert_eq!(
+        value("[1, 2, 3, 4].map(|x| if x % 2 == 0 { x }).compact()"),
+        "[2, 4]"
+    );
+    assert_eq!(
+        value("compact([none, 0, false, \"\", [], (|| { let a = 1; })()])"),
+        "[0, false, \"\", []]"
+    );
+    assert_eq!(error("compact(\"abc\");"), "compact expects a list");
+}
+
+#[test]
+fn slice_takes_part_of_a_string_or_list() {
+    assert_eq!(
+        value("[\"hello\".slice(1, 3), slice(\"héllo\", -4, -1), \"abc\".slice(-10, 10), slice(\"abc\", 2, 1)]"),
+        "[\"el\", \"éll\", \"abc\", \"\"]"
+    );
+    assert_eq!(
+        value("let li = [1, 2, 3, 4]; [li.slice(1, 3), slice(li, -3, -1), li.slice(-9, 2), slice(li, 3, 99), li]"),
+        "[[2, 3], [2, 3], [1, 2], [4], [1, 2, 3, 4]]"
+    );
+    assert_eq!(
+        error("slice({a: 1}, 0, 1);"),
+        "slice expects a string or list and two integers"
+    );
+}
+
+#[test]
+fn negative_indices_count_from_the_end() {
+    assert_eq!(
+        value("let l = [1, 2, 3]; [l[-1], l[-3], \"héllo\"[-4]]"),
+        "[3, 1, \"é\"]"
+    );
+    assert_eq!(
+        value("let l = [1, 2, 3]; l[-1] = 9; let o = {a: [1, 2]}; o.a[-2] = 5; [l, o]"),
+        "[[1, 2, 9], {a: [5, 2]}]"
+    );
+    assert_eq!(
+        error("let l = [1, 2, 3]; l[-4];"),
+        "Index -4 (position -1) is out of bounds for length 3"
+    );
+    assert_eq!(
+        error("let l = [1, 2, 3]; l[-4] = 1;"),
+        "Index -4 (position -1) is out of bounds for length 3"
+    );
+}
+
+#[test]
+fn sort_and_reverse_in_place_change_the_variable() {
+    assert_eq!(
+        output(
+            "let l = [3, 1, 2]; let copy = l; let r = sort!(l); print(l, r, copy); reverse!(l); print(l);
+            let o = {xs: [\"b\", \"a\"]}; sort!(o.xs); let nested = [[2, 1]]; reverse!(nested[0]); print(o, nested);"
+        ),
+        "[1, 2, 3] () [3, 1, 2]\n[3, 2, 1]\n{xs: [\"a\", \"b\"]} [[1, 2]]\n"
+    );
+    assert_eq!(
+        error("sort!([1]);"),
+        "sort! expects a variable as its first argument"
+    );
+    assert_eq!(error("let x = 1; sort!(x);"), "sort! expects a list, got 1");
+    assert_eq!(
+        error("let l = freeze([2, 1]); sort!(l);"),
+        "Cannot change a frozen List"
+    );
+}
+
+#[test]
+fn first_last_rest_and_init_take_apart_a_list() {
+    assert_eq!(
+        value("let l = [1, 2, 3]; [first(l), l.last(), rest(l), init(l), rest([]), init([]), l]"),
+        "[1, 3, [2, 3], [1, 2], [], [], [1, 2, 3]]"
+    );
+    assert_eq!(error("first([]);"), "first of an empty list");
+    assert_eq!(error("[].last();"), "last of an empty list");
+    assert_eq!(error("rest(1);"), "rest expects a list");
+}
+
+#[test]
+fn enumerate_pairs_elements_with_their_indices() {
+    assert_eq!(
+        output("for [i, v] in enumerate([\"a\", \"b\", \"c\"]) { print(i, v); }; print(enumerate([]));"),
+        "0 \"a\"\n1 \"b\"\n2 \"c\"\n[]\n"
+    );
+    assert_eq!(error("enumerate(\"ab\");"), "enumerate expects a list");
+}
+
+#[test]
+fn zip_pairs_two_lists_up_to_the_shorter_one() {
+    assert_eq!(
+        value("[zip([1, 2], [\"a\", \"b\"]), zip([1, 2, 3], [4]), zip([1], [4, 5]), zip([], [1])]"),
+        "[[[1, \"a\"], [2, \"b\"]], [[1, 4]], [[1, 4]], []]"
+    );
+    assert_eq!(error("zip([1], 2);"), "zip expects two lists");
+    assert_eq!(error("zip(\"ab\", [1]);"), "zip expects two lists");
+}
+
+#[test]
+fn reverse_returns_a_reversed_copy() {
+    assert_eq!(
+        output("let l = [1, [2, 3]]; let s = \"héllo\"; print(reverse(l), l, s.reverse(), s, reverse([]), reverse(\"\"));"),
+        "[[2, 3], 1] [1, [2, 3]] \"olléh\" \"héllo\" [] \"\"\n"
+    );
+    assert_eq!(error("reverse(1);"), "reverse expects a list or a string");
+}
+
+#[test]
+fn index_of_finds_equal_elements_and_substrings() {
+    assert_eq!(
+        value("[index_of([1, [2], {a: 1}], [2]), index_of([1, 2], 2.0), index_of([{a: [1]}], {a: [1.0]}), [1, 2, 2].index_of(2), index_of([1], 5)]"),
+        "[1, 1, 0, 1, none]"
+    );
+    assert_eq!(
+        value("[index_of(\"héllo\", \"l\"), \"hello\".index_of(\"llo\"), index_of(\"abc\", \"\"), index_of(\"abc\", \"z\")]"),
+        "[2, 2, 0, none]"
+    );
+    assert_eq!(
+        error("index_of(\"a\", 1);"),
+        "index_of expects a list and an item or two strings"
+    );
+}
+
+#[test]
+fn nested_elements_can_be_assigned() {
+    assert_eq!(
+        output("let g = [[0, 0], [0, [1, 2]]]; g[1][0] = 5; g[1][1][0] = 7; g[-1][-1][-1] = 9; g[0][1] += 3; print(g, g[1][0]);"),
+        "[[0, 3], [5, [7, 9]]] 5\n"
+    );
+    assert_eq!(
+        error("let g = [[0]]; g[1][0] = 1;"),
+        "Index 1 is out of bounds for length 1"
+    );
+    assert_eq!(
+        error("let g = [1]; g[0][0] = 1;"),
+        "1 cannot be indexed by 0"
+    );
+}
+
+#[test]
+fn insert_and_remove_at_change_the_list_in_place() {
+    assert_eq!(
+        output(
+            "let l = [2, 4]; insert!(l, 0, 1); insert!(l, 2, 3); let r = insert!(l, 4, 5); insert!(l, -1, 6); print(l, r);
+            print(remove_at!(l, 1), remove_at!(l, -1), remove_at!(l, 0), l);"
+        ),
+        "[1, 2, 3, 4, 5, 6] ()\n2 6 1 [3, 4, 5]\n"
+    );
+    assert_eq!(
+        error("let l = [1]; insert!(l, 3, 0);"),
+        "Cannot insert at index 3 into a list of length 1"
+    );
+    assert_eq!(
+        error("let l = []; remove_at!(l, 0);"),
+        "Index 0 is out of bounds for length 0"
+    );
+}
+
+#[test]
+fn sort_returns_a_sorted_copy_in_both_call_forms() {
+    assert_eq!(
+        output(
+            "let xs = [3, 1, 2]; print(sort(xs), xs.sort(), xs, sort([\"b\", \"a\"]), sort([]));"
+        ),
+        "[1, 2, 3] [1, 2, 3] [3, 1, 2] [\"a\", \"b\"] []\n"
+    );
+    assert_eq!(error("sort(\"ba\");"), "sort expects a list");
+    assert_eq!(error("{a: 1}.sort();"), "sort expects a list");
+}
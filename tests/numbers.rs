@@ -0,0 +1,314 @@
+mod common;
+
+use common::*;
+
+#[test]
+fn arithmetic_promotes_to_float_when_either_operand_is_a_float() {
+    assert_eq!(
+        value("[1 + 2, 1 + 2.0, 1.5 * 2, 3 - 0.5]"),
+        "[3, 3.0, 3.0, 2.5]"
+    );
+    assert_eq!(
+        value("[type(1 + 2), type(1 + 2.0)]"),
+        "[\"number\", \"number\"]"
+    );
+}
+
+#[test]
+fn division_yields_a_float_and_floor_division_an_int() {
+    assert_eq!(
+        value("[7 / 2, 6 / 2, 7 // 2, 7.0 // 2]"),
+        "[3.5, 3.0, 3, 3.0]"
+    );
+}
+
+#[test]
+fn floor_division_rounds_towards_negative_infinity() {
+    assert_eq!(
+        value("[7 // (0 - 2), (0 - 7) // 2, (0 - 7) // (0 - 2)]"),
+        "[-4, -4, 3]"
+    );
+    assert_eq!(
+        value("[7.0 // (0 - 2), (0 - 7.0) // 2, (0 - 7.0) // (0 - 2)]"),
+        "[-4.0, -4.0, 3.0]"
+    );
+}
+
+#[test]
+fn modulo_has_the_sign_of_the_divisor() {
+    assert_eq!(
+        value("[7 % 3, 7 % (0 - 3), (0 - 7) % 3, (0 - 7) % (0 - 3)]"),
+        "[1, -2, 2, -1]"
+    );
+    assert_eq!(value("[7.5 % (0 - 2), (0 - 7.5) % 2]"), "[-0.5, 0.5]");
+}
+
+#[test]
+fn a_leading_sign_binds_tighter_than_any_binary_operator() {
+    assert_eq!(
+        value(
+            "[-7 // 2 == -4, -7 % 3 == 2, -7 // 2, -7 % 3, -(7 // 2), 10 + -7 // 2, 10 - -7 // 2]"
+        ),
+        "[true, true, -4, 2, -3, 6, 14]"
+    );
+    assert_eq!(
+        value("let x = 5; [-x % 3, - -x, -+x, 2 * -3 // 4]"),
+        "[1, 5, -5, -2]"
+    );
+}
+
+#[test]
+fn floor_division_and_modulo_agree() {
+    let program = r#"
+        let ok = true;
+        for a in [7, 0 - 7, 6, 0 - 6, 0] {
+            for b in [2, 0 - 2, 3, 0 - 3] {
+                if a != (a // b) * b + a % b { ok = false; };
+            };
+        };
+        ok
+    "#;
+    assert_eq!(value(program), "true");
+}
+
+#[test]
+fn indices_must_be_integers() {
+    assert_eq!(value("[10, 20, 30][1]"), "20");
+    assert_eq!(
+        error("[10, 20, 30][1.5]"),
+        "index must be an integer, got 1.5"
+    );
+    assert_eq!(error("\"abc\"[0.5]"), "index must be an integer, got 0.5");
+}
+
+#[test]
+fn loop_counters_are_integers() {
+    assert_eq!(
+        output("for i in range(3) { print(i, i / 2); };"),
+        "0 0.0\n1 0.5\n2 1.0\n"
+    );
+}
+
+#[test]
+fn wrapping_builtins_wrap_at_the_i64_boundary() {
+    assert_eq!(
+        output(
+            "const MAX = 9223372036854775807; const MIN = 0 - MAX - 1;
+            print(wrapping_add(MAX, 1), wrapping_sub(MIN, 1), wrapping_mul(MIN, 0 - 1), wrapping_mul(3, 4));"
+        ),
+        "-9223372036854775808 9223372036854775807 -9223372036854775808 12\n"
+    );
+    assert_eq!(
+        error("9223372036854775807 + 1;"),
+        "arithmetic overflow in 9223372036854775807 + 1"
+    );
+    assert_eq!(
+        error("wrapping_add(1, 1.5);"),
+        "Function 'wrapping_add' expects two integers"
+    );
+}
+
+#[test]
+fn math_builtins_round_and_take_roots() {
+    assert_eq!(value("[sqrt(16), sqrt(2.25)]"), "[4.0, 1.5]");
+    assert_eq!(value("[abs(0 - 3), abs(0 - 2.5), abs(4)]"), "[3, 2.5, 4]");
+    assert_eq!(
+        value("[floor(2.7), floor(0 - 2.5), ceil(2.1), ceil(0 - 2.1), floor(3)]"),
+        "[2.0, -3.0, 3.0, -2.0, 3]"
+    );
+    assert_eq!(
+        value("[round(2.5), round(0 - 2.5), round(2.4)]"),
+        "[3.0, -3.0, 2.0]"
+    );
+}
+
+#[test]
+fn math_builtins_reject_results_they_cannot_represent() {
+    assert_eq!(
+        error("sqrt(0 - 1);"),
+        "cannot take the square root of negative number -1"
+    );
+    assert_eq!(
+        error("abs(0 - 9223372036854775807 - 1);"),
+        "abs of -9223372036854775808 overflows"
+    );
+}
+
+#[test]
+fn min_and_max_take_arguments_or_a_list() {
+    assert_eq!(
+        value("[max(3, 7, 2), min(3, 7, 2), max(1, 2.5)]"),
+        "[7, 2, 2.5]"
+    );
+    assert_eq!(
+        value("[max([3, 7, 2]), min([3, 1.5, 2]), max([\"a\", \"b\"])]"),
+        "[7, 1.5, \"b\"]"
+    );
+}
+
+#[test]
+fn min_and_max_reject_empty_lists_and_mixed_elements() {
+    assert_eq!(error("max([]);"), "max of an empty list");
+    assert_eq!(
+        error("min(1);"),
+        "min expects a list or at least two values"
+    );
+    assert_eq!(
+        error("max([1, 2, \"a\", 3]);"),
+        "max cannot compare element 2 (\"a\") with 2"
+    );
+    assert_eq!(
+        error("min(1, [2]);"),
+        "min cannot compare element 1 ([2]) with 1"
+    );
+}
+
+#[test]
+fn parse_number_reads_signed_integers_and_decimals() {
+    assert_eq!(
+        value("[parse_number(\"42\"), parse_number(\" -12 \"), parse_number(\"+3.5\"), parse_number(\"\\t0.25\\n\")]"),
+        "[42, -12, 3.5, 0.25]"
+    );
+    assert_eq!(
+        error("parse_number(\"12abc\");"),
+        "cannot parse \"12abc\" as a number"
+    );
+    assert_eq!(
+        error("parse_number(\"inf\");"),
+        "cannot parse \"inf\" as a number"
+    );
+}
+
+#[test]
+fn bitwise_not_flips_the_bits_of_an_integer() {
+    assert_eq!(
+        value("[~0 == -1, ~5, -~1, ~~7, ~-1, 2 * ~1, 2 * -3, 7 // -2]"),
+        "[true, -6, 2, 7, 0, -4, -6, -4]"
+    );
+    assert_eq!(error("~1.5;"), "Bitwise Not for 1.5 is not implemented!");
+}
+
+#[test]
+fn division_modulo_and_power_name_the_types_of_non_numbers() {
+    assert_eq!(error("\"a\" / 2;"), "Cannot divide String by Int");
+    assert_eq!(error("[1] // 2;"), "Cannot floor divide List by Int");
+    assert_eq!(error("1 % \"a\";"), "Cannot take Int modulo String");
+    assert_eq!(
+        error("pow(\"a\", 2);"),
+        "Cannot raise String to the power of Int"
+    );
+    assert_eq!(
+        error("pow(2, true);"),
+        "Cannot raise Int to the power of Boolean"
+    );
+}
+
+#[test]
+fn pow_rejects_results_that_are_not_finite() {
+    assert_eq!(
+        value("[pow(2, -1), pow(4, 0.5), pow(0, 0)]"),
+        "[0.5, 2.0, 1]"
+    );
+    assert_eq!(error("pow(0, 0 - 1);"), "pow(0, -1) has no finite result");
+    assert_eq!(
+        error("pow(0 - 1, 0.5);"),
+        "pow(-1, 0.5) has no finite result"
+    );
+    assert_eq!(
+        error("pow(10.0, 400);"),
+        "pow(10.0, 400) has no finite result"
+    );
+    assert_eq!(
+        error("pow(10, 400);"),
+        "arithmetic overflow in pow(10, 400)"
+    );
+}
+
+#[test]
+fn arithmetic_overflow_is_an_error_at_the_bounds() {
+    let program = "const MAX = 9223372036854775807; const MIN = 0 - MAX - 1;";
+    let fails = |expr: &str| error(&format!("{} {};", program, expr));
+    assert_eq!(
+        value(&format!(
+            "{} [MAX - 1 + 1, MIN + 1 - 1, MIN // 1, MAX % -1]",
+            program
+        )),
+        "[9223372036854775807, -9223372036854775808, -9223372036854775808, 0]"
+    );
+    assert_eq!(
+        fails("MIN - 1"),
+        "arithmetic overflow in -9223372036854775808 - 1"
+    );
+    assert_eq!(
+        fails("MAX * 2"),
+        "arithmetic overflow in 9223372036854775807 * 2"
+    );
+    assert_eq!(
+        fails("MIN // -1"),
+        "arithmetic overflow in -9223372036854775808 // -1"
+    );
+    assert_eq!(
+        fails("MIN % -1"),
+        "arithmetic overflow in -9223372036854775808 % -1"
+    );
+    assert_eq!(
+        fails("-MIN"),
+        "arithmetic overflow in -(-9223372036854775808)"
+    );
+    assert_eq!(fails("1 % 0"), "Cannot take 1 modulo zero");
+    assert_eq!(fails("1.5 / 0"), "Cannot divide 1.5 by zero");
+}
+
+#[test]
+fn float_arithmetic_without_a_finite_result_is_an_error() {
+    let program = "let big = pow(10.0, 300);";
+    assert_eq!(value(&format!("{} big / big * 2.5", program)), "2.5");
+    for (op, operand) in [
+        ("*", "10000000000.0"),
+        ("/", "0.0000000001"),
+        ("//", "0.0000000001"),
+    ] {
+        let message = error(&format!("{} big {} {};", program, op, operand));
+        assert!(
+            message.starts_with("arithmetic overflow in 1000"),
+            "{}",
+            message
+        );
+        assert!(
+            message.ends_with(&format!(".0 {} {}", op, operand)),
+            "{}",
+            message
+        );
+    }
+    let huge = format!("{} let huge = big * pow(10.0, 8);", program);
+    for (expr, op) in [("huge + huge", " + "), ("-huge - huge", " - ")] {
+        let message = error(&format!("{} {};", huge, expr));
+        assert!(
+            message.starts_with("arithmetic overflow in "),
+            "{}",
+            message
+        );
+        assert!(message.contains(op), "{}", message);
+    }
+}
+
+#[test]
+fn sum_and_product_reduce_a_list_of_numbers() {
+    assert_eq!(
+        value("[sum([1, 2, 3]), sum([1, 2.5]), sum([]), product([2, 3]), product([]), product([1.5, 2]), [1, 2].sum()]"),
+        "[6, 3.5, 0, 6, 1, 3.0, 3]"
+    );
+    assert_eq!(
+        error("sum([1, \"a\"]);"),
+        "sum expects a list of numbers, got \"a\""
+    );
+    assert_eq!(
+        error("product([2, [1]]);"),
+        "product expects a list of numbers, got [1]"
+    );
+    assert_eq!(error("sum(1);"), "sum expects a list");
+    assert_eq!(
+        error("sum([9223372036854775807, 1]);"),
+        "arithmetic overflow in 9223372036854775807 + 1"
+    );
+}
@@ -0,0 +1,134 @@
+mod common;
+
+use common::*;
+
+#[test]
+fn keys_and_values_list_the_fields() {
+    assert_eq!(
+        value("let o = {a: 1, b: [2], c: \"x\"}; [o.keys(), values(o), keys({})]"),
+        "[[\"a\", \"b\", \"c\"], [1, [2], \"x\"], []]"
+    );
+    assert_eq!(
+        value(
+            "let o = {x: 1, y: 2}; let ks = keys(o); let vs = o.values(); [ks[1], vs[1], o[ks[1]]]"
+        ),
+        "[\"y\", 2, 2]"
+    );
+}
+
+#[test]
+fn keys_of_something_else_is_an_error() {
+    assert_eq!(error("keys([1]);"), "keys expects an object");
+    assert_eq!(error("\"s\".values();"), "values expects an object");
+}
+
+#[test]
+fn objects_keep_the_order_fields_were_defined_in() {
+    let program = "let o = {zeta: 1, alpha: {y: 2, b: 3}, mid: [4]};";
+    assert_eq!(
+        output(&format!("{} print(o, o.keys(), values(o), to_list(o));", program)),
+        "{zeta: 1, alpha: {y: 2, b: 3}, mid: [4]} [\"zeta\", \"alpha\", \"mid\"] [1, {y: 2, b: 3}, [4]] \
+            [[\"zeta\", 1], [\"alpha\", {y: 2, b: 3}], [\"mid\", [4]]]\n"
+    );
+    assert_eq!(
+        output(&format!("{} for [key, _] in o {{ print(key); }};", program)),
+        "\"zeta\"\n\"alpha\"\n\"mid\"\n"
+    );
+    assert_eq!(
+        value(&format!("{} o.map(|k, v| k).values()", program)),
+        "[\"zeta\", \"alpha\", \"mid\"]"
+    );
+}
+
+#[test]
+fn changing_objects_keeps_the_order_of_the_other_fields() {
+    assert_eq!(
+        output(
+            "let o = {c: 1, a: 2, b: 3}; o.c = 4; remove!(o, \"a\"); print(o, o + {a: 5, c: 6});"
+        ),
+        "{c: 4, b: 3} {c: 6, b: 3, a: 5}\n"
+    );
+}
+
+#[test]
+fn objects_serialize_the_same_way_every_time() {
+    let program = "{zeta: 1, alpha: {y: 2, b: 3}, mid: [4], k: none}";
+    let first = slang::run(program).unwrap().to_json();
+    assert_eq!(
+        first,
+        r#"{"zeta":1,"alpha":{"y":2,"b":3},"mid":[4],"k":null}"#
+    );
+    for _ in 0..10 {
+        assert_eq!(slang::run(program).unwrap().to_json(), first);
+    }
+    assert_eq!(
+        value(&format!("to_string(parse_json({:?}))", first)),
+        "\"{zeta: 1, alpha: {y: 2, b: 3}, mid: [4], k: none}\""
+    );
+}
+
+#[test]
+fn merging_leaves_the_sources_unchanged() {
+    assert_eq!(
+        output(
+            "let a = {x: 1, inner: {y: 2}}; let b = {x: 2, inner: {z: 3}}; \
+             let m = merge(a, b); let d = a.merge_deep(b); m.x = 9; d.inner.y = 8; \
+             print(m, d, a, b);"
+        ),
+        "{x: 9, inner: {z: 3}} {x: 2, inner: {y: 8, z: 3}} {x: 1, inner: {y: 2}} {x: 2, inner: {z: 3}}\n"
+    );
+}
+
+#[test]
+fn merging_frozen_objects_returns_a_new_object() {
+    assert_eq!(
+        output(
+            "let a = deep_freeze({x: 1, inner: {y: 2}}); let b = freeze({inner: {z: 3}}); \
+             let d = merge_deep(a, b); d.inner.y = 5; print(d, is_frozen(d), a);"
+        ),
+        "{x: 1, inner: {y: 5, z: 3}} false {x: 1, inner: {y: 2}}\n"
+    );
+    assert_eq!(error("merge({a: 1}, [1]);"), "merge expects two objects");
+}
+
+#[test]
+fn nested_fields_can_be_assigned() {
+    assert_eq!(
+        output(
+            "let o = {a: {b: {c: 1}}, l: [{x: 1}]}; o.a.b = 5; print(o);
+            let p = {a: {b: {c: 1}}}; p.a.b.c = 3; o.l[0].x = 2; p[\"a\"][\"b\"][\"c\"] += 1; print(o, p);"
+        ),
+        "{a: {b: 5}, l: [{x: 1}]}\n{a: {b: 5}, l: [{x: 2}]} {a: {b: {c: 4}}}\n"
+    );
+    assert_eq!(
+        error("let o = {a: {}}; o.a.b.c = 1;"),
+        "Object has no field 'b'"
+    );
+    assert_eq!(
+        error("let o = {a: 1}; o.a.b = 1;"),
+        "Variable is not an object"
+    );
+}
+
+#[test]
+fn remove_deletes_a_field_in_place() {
+    assert_eq!(
+        output(
+            "let o = {a: 1, b: [2], c: 3}; let copy = o; let r = remove!(o, \"b\"); print(r, o, keys(o), copy);
+            let n = {inner: {a: 1}}; print(remove!(n.inner, \"a\"), n);"
+        ),
+        "[2] {a: 1, c: 3} [\"a\", \"c\"] {a: 1, b: [2], c: 3}\n1 {inner: {}}\n"
+    );
+    assert_eq!(
+        error("let o = {a: 1}; remove!(o, \"z\");"),
+        "Object has no field 'z'"
+    );
+    assert_eq!(
+        error("let o = freeze({a: 1}); remove!(o, \"a\");"),
+        "Cannot change a frozen Object"
+    );
+    assert_eq!(
+        error("let l = [1]; remove!(l, \"a\");"),
+        "remove! expects an object and a field name, got [1]"
+    );
+}
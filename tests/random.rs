@@ -0,0 +1,64 @@
+mod common;
+
+use common::*;
+use slang::*;
+
+fn seeded_output(seed: u64, program: &str) -> String {
+    let output = OutputBuffer::default();
+    let mut ctx = Context::new(".")
+        .with_output(Box::new(output.clone()))
+        .with_seed(seed);
+    execute_program(&mut ctx, program).unwrap();
+    output.contents()
+}
+
+#[test]
+fn uuids_are_random_version_4_uuids() {
+    assert_eq!(value("uuid() != uuid()"), "true");
+    let id = value("uuid()");
+    let id = id.trim_matches('"');
+    let groups: Vec<usize> = id.split('-').map(str::len).collect();
+    assert_eq!(groups, [8, 4, 4, 4, 12]);
+    assert_eq!(&id[14..15], "4");
+    assert!("89ab".contains(&id[19..20]), "{}", id);
+}
+
+#[test]
+fn seeded_uuids_are_reproducible() {
+    let program = "print(uuid(), uuid());";
+    assert_eq!(seeded_output(7, program), seeded_output(7, program));
+    assert_ne!(seeded_output(7, program), seeded_output(8, program));
+}
+
+#[test]
+fn seeded_random_numbers_are_reproducible() {
+    let program = "print(random(), random(), random(1, 100), random(-5, 5));";
+    assert_eq!(seeded_output(42, program), seeded_output(42, program));
+    assert_ne!(seeded_output(42, program), seeded_output(43, program));
+    // random and uuid draw from the same generator
+    assert_ne!(
+        seeded_output(42, "print(random());"),
+        seeded_output(42, "uuid(); print(random());")
+    );
+}
+
+#[test]
+fn random_stays_in_its_range() {
+    assert_eq!(
+        seeded_output(
+            1,
+            "let ok = true;
+            for i in 0..200 {
+                let f = random(); let n = random(-2, 2);
+                ok = ok && f >= 0.0 && f < 1.0 && n >= -2 && n <= 2 && random(1, 1) == 1;
+            };
+            print(ok);"
+        ),
+        "true\n"
+    );
+    assert_eq!(error("random(2, 1);"), "random expects 2 to be at most 1");
+    assert_eq!(
+        error("random(1.5, 2);"),
+        "random expects no arguments or two integers"
+    );
+}
@@ -0,0 +1,43 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+fn repl(input: &str) -> (String, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_slang"))
+        .arg("--repl")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    (
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+#[test]
+fn repl_keeps_bindings_and_prints_values() {
+    let (stdout, _) = repl("let x = 2;\nx * 21\nlet y = x;\n");
+    assert_eq!(stdout, "> > 42\n> > \n");
+}
+
+#[test]
+fn repl_continues_unclosed_input() {
+    let (stdout, _) = repl("let f = |n| {\nn + 1\n};\nf(1)\n");
+    assert_eq!(stdout, "> ... ... > 2\n> \n");
+}
+
+#[test]
+fn repl_reports_errors_and_goes_on() {
+    let (stdout, stderr) = repl("missing\n1 + 1\n");
+    assert_eq!(stdout, "> > 2\n> \n");
+    assert!(stderr.contains("variable is not defined"), "{}", stderr);
+}
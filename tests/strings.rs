@@ -0,0 +1,248 @@
+mod common;
+
+use common::*;
+
+#[test]
+fn currency_groups_thousands_and_rounds_to_cents() {
+    assert_eq!(
+        output(
+            "print(currency(1234567, \"$\"), currency(0 - 1234.567, \"€\"), currency(0.5, \"£\"));"
+        ),
+        "\"$1,234,567.00\" \"-€1,234.57\" \"£0.50\"\n"
+    );
+}
+
+#[test]
+fn currency_rejects_amounts_it_cannot_represent() {
+    assert_eq!(
+        error("currency(pow(10.0, 300), \"$\");"),
+        "Cannot format 1e300 as currency"
+    );
+    assert_eq!(
+        error("currency(\"1\", \"$\");"),
+        "Function 'currency' expects a number and a symbol"
+    );
+}
+
+#[test]
+fn split_once_and_rsplit_split_at_one_separator() {
+    assert_eq!(
+        value("let s = \"a=b=c\"; [s.split_once(\"=\"), s.rsplit(\"=\"), split_once(s, \"=\"), rsplit(s, \"=\")]"),
+        "[[\"a\", \"b=c\"], [\"a=b\", \"c\"], [\"a\", \"b=c\"], [\"a=b\", \"c\"]]"
+    );
+    assert_eq!(
+        value("[split_once(\"abc\", \"=\"), \"abc\".rsplit(\"=\")]"),
+        "[(), ()]"
+    );
+    assert_eq!(
+        error("split_once(\"a=b\");"),
+        "split_once expects a string and a separator"
+    );
+}
+
+#[test]
+fn pad_left_and_pad_right_align_columns() {
+    assert_eq!(
+        output("for n in [7, 42, 1234] { print(to_string(n).pad_left(5, \" \") + \"|\" + pad_right(to_string(n), 5, \".\")); };"),
+        "\"    7|7....\"\n\"   42|42...\"\n\" 1234|1234.\"\n"
+    );
+    assert_eq!(
+        value("[pad_left(\"toolong\", 3, \"0\"), \"é\".pad_right(3, \"ü\")]"),
+        "[\"toolong\", \"éüü\"]"
+    );
+    assert_eq!(
+        error("pad_left(\"1\", 3, \"ab\");"),
+        "pad_left expects a single fill character, got \"ab\""
+    );
+    assert_eq!(
+        error("pad_right(1, 3, \" \");"),
+        "pad_right expects a string, a width and a fill character"
+    );
+}
+
+#[test]
+fn count_matches_counts_without_overlaps() {
+    assert_eq!(
+        value("[\"aaaa\".count_matches(\"aa\"), count_matches(\"abababa\", \"aba\"), count_matches(\"abc\", \"x\")]"),
+        "[2, 2, 0]"
+    );
+    assert_eq!(
+        error("count_matches(\"abc\", \"\");"),
+        "count_matches needs a non-empty needle"
+    );
+    assert_eq!(
+        error("count_matches([1], \"a\");"),
+        "count_matches expects a string and a needle"
+    );
+}
+
+#[test]
+fn capitalize_and_title_case_uppercase_word_starts() {
+    assert_eq!(
+        value("[\"élan vital\".capitalize(), capitalize(\"\"), title_case(\"hello  wide\\tworld\"), \"straße ßtraße\".title_case()]"),
+        "[\"Élan vital\", \"\", \"Hello  Wide\tWorld\", \"Straße SStraße\"]"
+    );
+    assert_eq!(error("capitalize(1);"), "capitalize expects a string");
+}
+
+#[test]
+fn lines_splits_at_line_breaks_and_trims_each_line() {
+    assert_eq!(
+        value("[\"a b\\r\\n c\".lines(), lines(\"x\\n y \"), lines(\"\")]"),
+        "[[\"a b\", \"c\"], [\"x\", \"y\"], []]"
+    );
+    assert_eq!(error("lines(1);"), "lines expects a string");
+    assert_eq!(error("[\"a\"].lines();"), "lines expects a string");
+}
+
+#[test]
+fn words_splits_at_runs_of_whitespace() {
+    assert_eq!(
+        value("[\"  one two\\t\\tthree\\nfour \".words(), words(\"\"), words(\"   \")]"),
+        "[[\"one\", \"two\", \"three\", \"four\"], [], []]"
+    );
+    assert_eq!(error("words(1);"), "words expects a string");
+}
+
+#[test]
+fn split_and_join_are_inverses() {
+    assert_eq!(
+        value("[\"a,b,c\".split(\",\"), split(\"a,,b\", \",\"), split(\"hé\", \"\"), split(\"\", \",\")]"),
+        "[[\"a\", \"b\", \"c\"], [\"a\", \"\", \"b\"], [\"h\", \"é\"], [\"\"]]"
+    );
+    assert_eq!(
+        value("[join([\"a\", \"b\"], \", \"), [\"x\", 1, [2], none].join(\"-\"), join([], \",\")]"),
+        "[\"a, b\", \"x-1-[2]-none\", \"\"]"
+    );
+    assert_eq!(
+        value("let s = \"a b c\"; join(split(s, \" \"), \" \") == s"),
+        "true"
+    );
+    assert_eq!(
+        error("split([1], \",\");"),
+        "split expects a string and a separator"
+    );
+    assert_eq!(
+        error("join(\"ab\", \",\");"),
+        "join expects a list and a separator"
+    );
+}
+
+#[test]
+fn case_and_whitespace_are_changed_on_a_copy() {
+    assert_eq!(
+        output("let s = \"  MiXed Çase \"; print(s.to_upper(), to_lower(s), trim(s), s.trim().to_upper(), s);"),
+        "\"  MIXED ÇASE \" \"  mixed çase \" \"MiXed Çase\" \"MIXED ÇASE\" \"  MiXed Çase \"\n"
+    );
+    assert_eq!(error("to_upper(1);"), "to_upper expects a string");
+    assert_eq!(error("[\" a \"].trim();"), "trim expects a string");
+}
+
+#[test]
+fn replace_swaps_every_occurrence() {
+    assert_eq!(
+        value("[\"a-b-c\".replace(\"-\", \", \"), replace(\"aaa\", \"aa\", \"\"), \"abc\".replace(\"x\", \"y\")]"),
+        "[\"a, b, c\", \"a\", \"abc\"]"
+    );
+    assert_eq!(
+        error("replace(\"abc\", \"\", \"x\");"),
+        "replace needs a non-empty string to replace"
+    );
+    assert_eq!(
+        error("replace(\"abc\", 1, \"x\");"),
+        "replace expects three strings"
+    );
+}
+
+#[test]
+fn char_literals_are_one_character_strings() {
+    assert_eq!(
+        value("['a', '\\n' == \"\\n\", '\\t' == \"\\t\", '\\\\' == \"\\\\\", '\\'' == \"'\", 'é'.len()]"),
+        "[\"a\", true, true, true, true, 2]"
+    );
+    assert_eq!(error("'';"), "empty char literal");
+    assert_eq!(
+        error("'ab';"),
+        "char literal 'ab' must be a single character"
+    );
+    assert_eq!(error("'\\q';"), "unknown escape '\\q' in char literal");
+}
+
+#[test]
+fn chars_splits_a_string_into_characters() {
+    assert_eq!(
+        value("[\"héllo\".chars(), chars(\"\"), chars(\"a\\nb\")[1] == '\\n']"),
+        "[[\"h\", \"é\", \"l\", \"l\", \"o\"], [], true]"
+    );
+    assert_eq!(error("chars(1);"), "chars expects a string");
+}
+
+#[test]
+fn escapes_in_string_literals_become_the_characters_they_stand_for() {
+    assert_eq!(
+        value("\"a\\tb\\r\\n\\0\\\\\\\"\".chars()"),
+        "[\"a\", \"\t\", \"b\", \"\r\", \"\n\", \"\0\", \"\\\", \"\"\"]"
+    );
+    assert_eq!(output("print(\"line1\\nline2\");"), "\"line1\nline2\"\n");
+    assert_eq!(
+        error("\"a\\qb\";"),
+        "unknown escape '\\q' in string literal"
+    );
+    assert_eq!(error("\"abc\\\";"), "unterminated string literal");
+}
+
+#[test]
+fn strings_compare_by_their_characters() {
+    assert_eq!(
+        value("[\"apple\" < \"banana\", \"apple\" <= \"apple\", \"b\" > \"abc\", \"Z\" < \"a\", \"é\" > \"z\", \"a\" == \"a\", \"a\" != \"A\"]"),
+        "[true, true, true, true, true, true, true]"
+    );
+    assert_eq!(
+        value("[\"pear\", \"Apple\", \"apple\"].sort()"),
+        "[\"Apple\", \"apple\", \"pear\"]"
+    );
+    assert_eq!(
+        error("\"a\" < 1;"),
+        "Cannot compare String and Int with '<'"
+    );
+}
+
+#[test]
+fn format_fills_placeholders_in_order() {
+    assert_eq!(
+        value("[format(\"{} + {} = {}\", 1, 2.5, 3.5), format(\"{{}} {}}}\", \"s\"), format(\"no placeholders\")]"),
+        "[\"1 + 2.5 = 3.5\", \"{} s}\", \"no placeholders\"]"
+    );
+    assert_eq!(
+        value("format(\"{}\", [1, \"a\"]) == \"[1, \\\"a\\\"]\""),
+        "true"
+    );
+    assert_eq!(
+        error("format(\"{} {}\", 1);"),
+        "format string has 2 placeholders, but got 1 argument"
+    );
+    assert_eq!(
+        error("format(\"{}\", 1, 2);"),
+        "format string has 1 placeholder, but got 2 arguments"
+    );
+    assert_eq!(
+        error("format(\"}\");"),
+        "unmatched '}' in format string, use '}}' for a brace"
+    );
+}
+
+#[test]
+fn starts_with_and_ends_with_match_prefixes_and_suffixes() {
+    assert_eq!(
+        value("[starts_with(\"hello\", \"he\"), \"hello\".ends_with(\"lo\"), starts_with(\"hello\", \"lo\"), ends_with(\"hello\", \"he\"), starts_with(\"a\", \"\"), ends_with(\"\", \"\"), starts_with(\"\", \"a\")]"),
+        "[true, true, false, false, true, true, false]"
+    );
+    assert_eq!(
+        error("starts_with(1, \"a\");"),
+        "starts_with expects two strings"
+    );
+    assert_eq!(
+        error("\"a\".ends_with(1);"),
+        "ends_with expects two strings"
+    );
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use common::*;
+use slang::*;
+use std::{cell::Cell, rc::Rc};
+
+#[test]
+fn format_time_renders_known_epochs() {
+    assert_eq!(
+        output("print(format_time(0, \"%Y-%m-%d %H:%M:%S\"), format_time(1700000000123, \"%d.%m.%Y\"));"),
+        "\"1970-01-01 00:00:00\" \"14.11.2023\"\n"
+    );
+}
+
+#[test]
+fn parse_time_inverts_format_time() {
+    assert_eq!(
+        value("parse_time(\"2023-11-14 22:13:20\", \"%Y-%m-%d %H:%M:%S\")"),
+        "1700000000000"
+    );
+    assert_eq!(
+        value("let p = \"%Y-%m-%d %H:%M:%S\"; parse_time(format_time(86400000, p), p)"),
+        "86400000"
+    );
+}
+
+#[test]
+fn time_builtins_reject_bad_patterns_and_input() {
+    assert_eq!(
+        error("format_time(0, \"%Q\");"),
+        "invalid time format specifier '%Q'"
+    );
+    assert_eq!(
+        error("parse_time(\"2023-xx\", \"%Y-%m\");"),
+        "'2023-xx' does not match the pattern '%Y-%m'"
+    );
+}
+
+#[test]
+fn now_reads_the_clock_of_the_context() {
+    let ticks = Rc::new(Cell::new(1_700_000_000_000));
+    let clock = ticks.clone();
+    let (ctx, _, _) = capturing_context();
+    let mut ctx = ctx.with_clock(Box::new(move || {
+        clock.set(clock.get() + 250);
+        clock.get()
+    }));
+    let program = "let start = now(); [start, now() - start, format_time(start, \"%Y-%m-%d\")]";
+    assert_eq!(
+        execute_program(&mut ctx, program).unwrap().to_string(),
+        "[1700000000250, 250, \"2023-11-14\"]"
+    );
+    assert_eq!(ticks.get(), 1_700_000_000_500);
+}
+
+#[test]
+fn now_defaults_to_the_system_clock() {
+    let before = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as i64;
+    let now: i64 = value("now()").parse().unwrap();
+    assert!(now >= before && now < before + 60_000, "{} {}", before, now);
+}
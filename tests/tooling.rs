@@ -0,0 +1,251 @@
+mod common;
+
+use common::*;
+use slang::*;
+
+fn format_with(program: &str, indent_width: usize, use_tabs: bool, max: usize) -> String {
+    let options = FormatOptions {
+        indent_width,
+        use_tabs,
+        max_line_length: max,
+    };
+    match format_program(program, &options) {
+        Ok(s) => s,
+        Err(e) => panic!("cannot format: {}", e),
+    }
+}
+
+#[test]
+fn formatter_indents_with_the_configured_width() {
+    let program = "let f = |x| { if x > 1 { print(x); }; };";
+    assert_eq!(
+        format_with(program, 2, false, 100),
+        "let f = |x| {\n  if x > 1 {\n    print(x);\n  };\n};\n"
+    );
+    assert_eq!(
+        format_with(program, 4, false, 100),
+        "let f = |x| {\n    if x > 1 {\n        print(x);\n    };\n};\n"
+    );
+    assert_eq!(
+        format_with(program, 4, true, 100),
+        "let f = |x| {\n\tif x > 1 {\n\t\tprint(x);\n\t};\n};\n"
+    );
+}
+
+#[test]
+fn formatter_wraps_arguments_longer_than_the_line_length() {
+    let program = "print(111111111, 222222222, 333333333);";
+    assert_eq!(
+        format_with(program, 4, false, 100),
+        format!("{}\n", program)
+    );
+    assert_eq!(
+        format_with(program, 2, false, 30),
+        "print(\n  111111111,\n  222222222,\n  333333333\n);\n"
+    );
+}
+
+fn classes(source: &str) -> Vec<(String, TokenClass)> {
+    classify_tokens(source)
+        .into_iter()
+        .map(|t| (source[t.span].to_string(), t.class))
+        .collect()
+}
+
+#[test]
+fn highlighting_classifies_the_tokens_of_a_line() {
+    use TokenClass::*;
+    assert_eq!(
+        classes("let größe = sort!([2.5, 1]) != \"a\\\"b\"; # note\nx += 'c';"),
+        [
+            ("let", Keyword),
+            ("größe", Identifier),
+            ("=", Operator),
+            ("sort!", Identifier),
+            ("(", Punctuation),
+            ("[", Punctuation),
+            ("2.5", Number),
+            (",", Punctuation),
+            ("1", Number),
+            ("]", Punctuation),
+            (")", Punctuation),
+            ("!=", Operator),
+            ("\"a\\\"b\"", String),
+            (";", Punctuation),
+            ("# note", Comment),
+            ("x", Identifier),
+            ("+=", Operator),
+            ("'c'", String),
+            (";", Punctuation),
+        ]
+        .map(|(text, class)| (text.to_string(), class))
+    );
+}
+
+#[test]
+fn highlighting_keeps_going_after_invalid_tokens() {
+    use TokenClass::*;
+    assert_eq!(
+        classes("if true { x @ 1 } \"open"),
+        [
+            ("if", Keyword),
+            ("true", Keyword),
+            ("{", Punctuation),
+            ("x", Identifier),
+            ("@", Punctuation),
+            ("1", Number),
+            ("}", Punctuation),
+            ("\"open", String),
+        ]
+        .map(|(text, class)| (text.to_string(), class))
+    );
+}
+
+#[test]
+fn highlighting_classifies_ranges_as_one_operator() {
+    use TokenClass::*;
+    assert_eq!(
+        classes("for i in 0..n { xs[1..=2]; f(...rest); };"),
+        [
+            ("for", Keyword),
+            ("i", Identifier),
+            ("in", Keyword),
+            ("0", Number),
+            ("..", Operator),
+            ("n", Identifier),
+            ("{", Punctuation),
+            ("xs", Identifier),
+            ("[", Punctuation),
+            ("1", Number),
+            ("..=", Operator),
+            ("2", Number),
+            ("]", Punctuation),
+            (";", Punctuation),
+            ("f", Identifier),
+            ("(", Punctuation),
+            ("...", Punctuation),
+            ("rest", Identifier),
+            (")", Punctuation),
+            (";", Punctuation),
+            ("}", Punctuation),
+            (";", Punctuation),
+        ]
+        .map(|(text, class)| (text.to_string(), class))
+    );
+}
+
+#[test]
+fn completion_suggests_builtins_and_variables_in_scope() {
+    let program = "let rate = 2; let f = |radius| { let inner = 1; }; ra";
+    assert_eq!(
+        complete(program, program.len()),
+        ["random", "range", "rate"]
+    );
+
+    let inside = "let rate = 2; let f = |radius| { ra";
+    assert_eq!(
+        complete(inside, inside.len()),
+        ["radius", "random", "range", "rate"]
+    );
+    assert!(!complete(program, program.len() - 2).contains(&"inner".to_string()));
+}
+
+#[test]
+fn hover_describes_constants_functions_and_builtins() {
+    let program = "let limit = 2 * 21; let f = |x| x; print(limit, f(1));";
+    let at = |word: &str| hover(program, program.rfind(word).unwrap()).unwrap();
+
+    let limit = at("limit");
+    assert_eq!(
+        (limit.name.as_str(), limit.kind),
+        ("limit", HoverKind::Variable)
+    );
+    assert!(matches!(limit.value, Some(VariableValue::Int(42))));
+
+    let f = at("f(");
+    assert_eq!(f.kind, HoverKind::Function);
+    assert!(f.value.is_none());
+
+    assert_eq!(at("print").kind, HoverKind::Builtin);
+    assert!(hover(program, program.find(';').unwrap()).is_none());
+}
+
+#[test]
+fn formatting_keeps_comments_in_place() {
+    let program = "# leading\nlet x = 1;   # trailing\nif x > 0 {\n# inside\nprint(x);\n};\n";
+    let formatted = format_with(program, 4, false, 100);
+    assert_eq!(
+        formatted,
+        "# leading\nlet x = 1; # trailing\nif x > 0 {\n    # inside\n    print(x);\n};\n"
+    );
+    assert_eq!(format_with(&formatted, 4, false, 100), formatted);
+}
+
+#[test]
+fn formatting_refuses_to_drop_a_comment() {
+    let options = FormatOptions::default();
+    for program in [
+        "let xs = [\n    1, # one\n    2,\n];",
+        "let o = {\n    a: 1, # one\n};",
+        "print(1, # one\n    2);",
+        "let f = |a, # one\n    b| a;",
+    ] {
+        let SyntaxError(message, location) = format_program(program, &options).expect_err(program);
+        assert_eq!(
+            message,
+            "cannot format a comment inside a list, object, call or parameter list"
+        );
+        let comment_line = program[..program.find('#').unwrap()].lines().count();
+        assert_eq!(location.map(|l| l.span.line), Some(comment_line));
+    }
+    assert_eq!(
+        format_with("let y = 1 + # mid\n    2;", 4, false, 100),
+        "let y = 1 + 2; # mid\n"
+    );
+}
+
+#[test]
+fn formatting_keeps_escapes_in_strings() {
+    let program = "print(\"a\\0b\\n\\t\\r\\\\\\\"c\\'\");";
+    let formatted = format_with(program, 4, false, 100);
+    assert_eq!(formatted, "print(\"a\\0b\\n\\t\\r\\\\\\\"c'\");\n");
+    assert!(!formatted.contains('\0'));
+    assert_eq!(output(&formatted), output(program));
+}
+
+#[test]
+fn formatting_keeps_the_order_of_object_fields() {
+    assert_eq!(
+        format_with("let o = {b: 1, a: {d: 2, c: 3}};", 4, false, 100),
+        "let o = {b: 1, a: {d: 2, c: 3}};\n"
+    );
+}
+
+#[test]
+fn formatter_keeps_only_the_parentheses_precedence_needs() {
+    let cases = [
+        ("(1 + 2) * 3", "(1 + 2) * 3"),
+        ("((1 * 2)) + 3", "1 * 2 + 3"),
+        ("1 - (2 - 3)", "1 - (2 - 3)"),
+        ("(1 - 2) - 3", "1 - 2 - 3"),
+        ("6 / (2 * 3) + -(1 + 2)", "6 / (2 * 3) + -(1 + 2)"),
+        ("2 * (-3)", "2 * -3"),
+        ("(-7) // 2 - ~(~1)", "-7 // 2 - ~~1"),
+        ("-(7 // 2) % 3", "-(7 // 2) % 3"),
+        ("1 - (-3)", "1 - -3"),
+        (
+            "!(true && false) || (true && false)",
+            "!(true && false) || (true && false)",
+        ),
+        ("(true && false) || true", "true && false || true"),
+        ("(1 + 2 < 4) == (2 > 1)", "1 + 2 < 4 == (2 > 1)"),
+        ("true && (false || true)", "true && (false || true)"),
+        ("(1 + 2).to_string()", "(1 + 2).to_string()"),
+    ];
+    for (source, formatted) in cases {
+        let program = format!("print({});", source);
+        let result = format_with(&program, 4, false, 100);
+        assert_eq!(result, format!("print({});\n", formatted));
+        assert_eq!(output(&program), output(&result));
+    }
+}
@@ -0,0 +1,203 @@
+mod common;
+
+use common::*;
+
+#[test]
+fn none_equals_only_none() {
+    assert_eq!(value("none == none"), "true");
+    assert_eq!(value("none != none"), "false");
+    assert_eq!(
+        value("[none == 0, none == false, none == \"\", none == []]"),
+        "[false, false, false, false]"
+    );
+    assert_eq!(value("0 != none"), "true");
+}
+
+#[test]
+fn none_prints_as_none() {
+    assert_eq!(
+        output("print(none, [none], {a: none});"),
+        "none [none] {a: none}\n"
+    );
+    assert_eq!(value("none"), "none");
+}
+
+#[test]
+fn functions_print_their_parameters() {
+    assert_eq!(
+        output("print(|a, b: 2, ...rest| a, || 1, |x| { x; });"),
+        "<fn(a, b, ...rest)> <fn()> <fn(x)>\n"
+    );
+}
+
+#[test]
+fn type_names_every_kind_of_value() {
+    assert_eq!(
+        value(
+            "let unit = || { let a = 1; };
+            [type(1), type(1.5), type(true), type(\"s\"), type([]), type({a: 1}), type(|x| x),
+                type(unit()), type(none), type(bytes_from_string(\"b\")), type(freeze([1]))]"
+        ),
+        "[\"number\", \"number\", \"boolean\", \"string\", \"list\", \"object\", \"function\", \
+            \"unit\", \"none\", \"bytes\", \"list\"]"
+    );
+}
+
+#[test]
+fn to_string_matches_what_print_shows() {
+    assert_eq!(
+        value(
+            r#"[to_string(42) == "42", to_string(2.0) == "2.0", to_string("s") == "\"s\"",
+                to_string([1, ["a", []]]) == "[1, [\"a\", []]]",
+                to_string({a: {b: [1]}}) == "{a: {b: [1]}}"]"#
+        ),
+        "[true, true, true, true, true]"
+    );
+    assert_eq!(
+        output(r#"let v = {a: [1, "x"], b: none}; print(v); print(to_string(v).len());"#),
+        "{a: [1, \"x\"], b: none}\n22\n"
+    );
+}
+
+#[test]
+fn lists_objects_and_bytes_are_compared_by_contents() {
+    assert_eq!(
+        value("[[1, [2, 3]] == [1, [2, 3]], [1, [2, 3]] == [1, [2, 4]], [1] == [1, 2], [] == []]"),
+        "[true, false, false, true]"
+    );
+    assert_eq!(
+        value("[{a: 1, b: {c: [1]}} == {b: {c: [1.0]}, a: 1}, {a: {c: [1]}} == {a: {c: [2]}}, {a: 1} == {b: 1}, {a: 1} == {a: 1, b: 2}]"),
+        "[true, false, false, false]"
+    );
+    assert_eq!(
+        value("[[1] != [2], {a: [1]} != {a: [1]}, [] == {}, freeze([1, [2]]) == [1, [2]], deep_freeze({a: [1]}) != {a: [2]}]"),
+        "[true, false, false, true, true]"
+    );
+    assert_eq!(
+        value("[bytes_from_string(\"ab\") == hex_decode(\"6162\"), bytes_from_string(\"a\") == bytes_from_string(\"b\")]"),
+        "[true, false]"
+    );
+}
+
+#[test]
+fn ranges_equal_lists_with_the_same_elements() {
+    assert_eq!(
+        value("[0..3 == range(3), 0..3 == range(4), 1..=2 == [1, 2]]"),
+        "[true, false, true]"
+    );
+    assert_eq!(
+        error("let r = 0..9000000000000000000;"),
+        "range 0..9000000000000000000 is too long to be a list"
+    );
+    assert_eq!(
+        error("range(1099511627776);"),
+        "range 0..1099511627776 is too long to be a list"
+    );
+    assert_eq!(
+        error("downto(1099511627776, 1);"),
+        "range 1099511627776..0 is too long to be a list"
+    );
+    assert_eq!(
+        value("range(0, 1099511627776, 68719476736)"),
+        "[0, 68719476736, 137438953472, 206158430208, 274877906944, 343597383680, 412316860416, 481036337152, 549755813888, 618475290624, 687194767360, 755914244096, 824633720832, 893353197568, 962072674304, 1030792151040]"
+    );
+}
+
+#[test]
+fn only_numbers_and_strings_can_be_ordered() {
+    assert_eq!(
+        value("[1 < 2.5, \"apple\" < \"banana\", \"Z\" < \"a\", 2 >= 2.0, true == true]"),
+        "[true, true, true, true, true]"
+    );
+    assert_eq!(
+        error("true < 1;"),
+        "Cannot compare Boolean and Int with '<'"
+    );
+    assert_eq!(
+        error("true > false;"),
+        "Cannot compare Boolean and Boolean with '>'"
+    );
+    assert_eq!(
+        error("1 <= \"a\";"),
+        "Cannot compare Int and String with '<='"
+    );
+    assert_eq!(error("[1] < [2];"), "Cannot compare List and List with '<'");
+    assert_eq!(error("none >= 1;"), "Cannot compare None and Int with '>='");
+}
+
+#[test]
+fn deep_freeze_protects_every_level() {
+    assert_eq!(
+        output(
+            "let shallow = freeze({a: [1]}); shallow.a[0] = 3;
+            let deep = deep_freeze({a: [1, {b: [2]}]});
+            print(shallow, is_frozen(shallow), is_frozen(shallow.a), is_frozen(deep.a[1].b), is_frozen(1), is_frozen([1]));"
+        ),
+        "{a: [3]} true false true true false\n"
+    );
+    let deep = "let deep = deep_freeze({a: [1, {b: [2]}]});";
+    for change in [
+        "deep.a[1].b[0] = 3;",
+        "deep.a[0] = 3;",
+        "sort!(deep.a[1].b);",
+        "deep.a = [];",
+    ] {
+        let message = error(&format!("{} {}", deep, change));
+        assert!(
+            message.starts_with("Cannot change a frozen"),
+            "{}: {}",
+            change,
+            message
+        );
+    }
+}
+
+#[test]
+fn to_list_converts_every_kind_of_collection() {
+    assert_eq!(
+        value("[to_list(\"héy\"), to_list(0..3), to_list({b: 1, a: [2]}), to_list([1, [2]]), to_list(bytes_from_string(\"ab\")), \"\".to_list()]"),
+        "[[\"h\", \"é\", \"y\"], [0, 1, 2], [[\"b\", 1], [\"a\", [2]]], [1, [2]], [97, 98], []]"
+    );
+    assert_eq!(error("to_list(1);"), "Cannot convert Int to a list");
+    assert_eq!(error("to_list(none);"), "Cannot convert None to a list");
+}
+
+#[test]
+fn plus_concatenates_lists_and_merges_objects() {
+    assert_eq!(
+        output("let a = [1]; let b = a + [2, [3]]; let o = {x: 1, y: 2}; print(a, b, [] + [], o + {y: 3, z: 4}, o);"),
+        "[1] [1, 2, [3]] [] {x: 1, y: 3, z: 4} {x: 1, y: 2}\n"
+    );
+    assert_eq!(
+        error("[1] + 2;"),
+        "Addition between [1] and 2 is not implemented!"
+    );
+    assert_eq!(
+        error("{a: 1} + [1];"),
+        "Addition between {a: 1} and [1] is not implemented!"
+    );
+}
+
+#[test]
+fn clone_makes_an_independent_deep_copy() {
+    assert_eq!(
+        output(
+            "let original = {list: [1, {deep: [2]}], name: \"a\"};
+            let copy = clone(original); copy.list[1].deep[0] = 3; copy.name = \"b\"; insert!(copy.list, 0, 0);
+            let thawed = clone(deep_freeze([[1]])); thawed[0][0] = 2;
+            print(original, copy, thawed, is_frozen(thawed), clone(1), clone(\"s\"));"
+        ),
+        "{list: [1, {deep: [2]}], name: \"a\"} {list: [0, 1, {deep: [3]}], name: \"b\"} [[2]] false 1 \"s\"\n"
+    );
+}
+
+#[test]
+fn literals_arguments_and_parameters_may_end_with_a_comma() {
+    assert_eq!(
+        value("let f = |a, b,| a + b; [[1, 2, 3,], {a: 1, b: 2,}, f(1, 2,), [1,], [\n  \"x\",\n  \"y\",\n]]"),
+        "[[1, 2, 3], {a: 1, b: 2}, 3, [1], [\"x\", \"y\"]]"
+    );
+    assert_eq!(error("[1,,];"), "Empty expr");
+    assert_eq!(error("[,];"), "Empty expr");
+    assert_eq!(error("print(,);"), "Empty expr");
+}
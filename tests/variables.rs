@@ -0,0 +1,91 @@
+mod common;
+
+use common::*;
+
+#[test]
+fn constants_can_be_read_but_not_assigned() {
+    assert_eq!(
+        output("const limit = 3; let f = || limit + 1; print(limit * 2, f());"),
+        "6 4\n"
+    );
+    assert_eq!(
+        error("const limit = 3; limit = 4;"),
+        "cannot assign to constant"
+    );
+    assert_eq!(
+        error("const limit = 3; limit += 1;"),
+        "cannot assign to constant"
+    );
+    assert_eq!(
+        error("const limit = 3; if true { limit = 4; };"),
+        "cannot assign to constant"
+    );
+    assert_eq!(
+        error("const limit = 3; let f = || { limit = 4; }; f();"),
+        "cannot assign to constant"
+    );
+}
+
+#[test]
+fn constants_cannot_be_changed_in_place() {
+    assert_eq!(
+        error("const xs = [1, 2]; xs[0] = 5;"),
+        "cannot assign to constant"
+    );
+    assert_eq!(
+        error("const o = {a: {b: 1}}; o.a = 9;"),
+        "cannot assign to constant"
+    );
+    assert_eq!(
+        error("const o = {a: {b: 1}}; o.a.b = 9;"),
+        "cannot assign to constant"
+    );
+    assert_eq!(
+        error("const xs = [2, 1]; sort!(xs);"),
+        "cannot assign to constant"
+    );
+    assert_eq!(
+        output("const xs = [2, 1]; let ys = xs; ys[0] = 5; sort!(ys); print(xs, ys, xs.sort());"),
+        "[2, 1] [1, 5] [1, 2]\n"
+    );
+}
+
+#[test]
+fn let_shadows_a_variable_in_the_same_and_nested_blocks() {
+    let program = r#"
+        let a = 1;
+        let a = a + 1;
+        print(a);
+        {
+            let a = "inner";
+            print(a);
+        };
+        print(a);
+        const c = 1;
+        let c = 2;
+        c = 3;
+        print(c);
+    "#;
+    assert_eq!(output(program), "2\n\"inner\"\n2\n3\n");
+    assert_eq!(
+        output("let f = |n| { let n = n * 2; n }; print(f(3), f(4));"),
+        "6 8\n"
+    );
+}
+
+#[test]
+fn let_without_a_value_defines_none_until_assigned() {
+    let program = r#"
+        let x;
+        print(x, x == none);
+        let sign;
+        if x == none { sign = "unset"; } else { sign = "set"; };
+        x = 5;
+        print(sign, x);
+    "#;
+    assert_eq!(output(program), "none true\n\"unset\" 5\n");
+    assert_eq!(
+        error("let x; x += 1;"),
+        "Addition between none and 1 is not implemented!"
+    );
+}